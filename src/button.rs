@@ -1,6 +1,14 @@
 use macroquad::{
-    color::WHITE,
-    texture::{draw_texture_ex, DrawTextureParams, Texture2D},
+    color::Color,
+    math::Vec2,
+    texture::{draw_texture_ex, DrawTextureParams, Image, Texture2D},
+    time::get_time,
+};
+
+use crate::{
+    settings::Renderer,
+    vector::{self, ButtonState},
+    Manipulation,
 };
 
 #[derive(Debug)]
@@ -8,11 +16,20 @@ pub enum ButtonEvent {
     Pressed(ButtonId),
 }
 
+#[derive(Debug, Clone, Copy, PartialEq)]
 #[non_exhaustive]
 pub enum MouseEvent {
-    Moved { x: f32, y: f32 },
-    Pressed { x: f32, y: f32 },
+    Moved {
+        x: f32,
+        y: f32,
+    },
+    Pressed {
+        x: f32,
+        y: f32,
+    },
     Released,
+    /// The pointer is gone, e.g. because the touch that was the pointer ended.
+    Exited,
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -23,21 +40,105 @@ pub enum ButtonId {
     VerticalDown(u8),
     RotateClockwise(u8),
     RotateAnticlockwise(u8),
+    Hint,
+    Undo,
+    Redo,
+    Settings,
+}
+
+impl ButtonId {
+    /// The manipulation that pressing the button makes, if any.
+    pub fn manipulation(&self) -> Option<Manipulation> {
+        match *self {
+            ButtonId::HorizontalLeft(row) => Some(Manipulation::SlideLeft(row)),
+            ButtonId::HorizontalRight(row) => Some(Manipulation::SlideRight(row)),
+            ButtonId::VerticalUp(col) => Some(Manipulation::SlideUp(col)),
+            ButtonId::VerticalDown(col) => Some(Manipulation::SlideDown(col)),
+            ButtonId::RotateClockwise(ring) => Some(Manipulation::RotateClockwise(ring)),
+            ButtonId::RotateAnticlockwise(ring) => Some(Manipulation::RotateAnticlockwise(ring)),
+            ButtonId::Hint | ButtonId::Undo | ButtonId::Redo | ButtonId::Settings => None,
+        }
+    }
+}
+
+/// The pixels of a texture that are opaque enough to be clicked.
+pub struct AlphaMask {
+    width: usize,
+    height: usize,
+    opaque: Vec<bool>,
+}
+
+impl AlphaMask {
+    const MIN_ALPHA: u8 = 128;
+
+    pub fn from_image(image: &Image) -> Self {
+        Self {
+            width: image.width as usize,
+            height: image.height as usize,
+            opaque: image
+                .get_image_data()
+                .iter()
+                .map(|pixel| pixel[3] >= Self::MIN_ALPHA)
+                .collect(),
+        }
+    }
+
+    /// Whether the pixel at the point is opaque, with the point relative to
+    /// the top left corner of the texture.
+    fn is_opaque(&self, x: f32, y: f32) -> bool {
+        if x < 0. || y < 0. {
+            return false;
+        }
+
+        let (col, row) = (x as usize, y as usize);
+        col < self.width && row < self.height && self.opaque[row * self.width + col]
+    }
+}
+
+/// Whether a point is inside a texture drawn with the given size at `position`
+/// and rotated around `pivot` the same way as [draw_texture_ex] does it. The
+/// mask is stretched to the size the texture is drawn with.
+fn hit_test(
+    point: Vec2,
+    position: Vec2,
+    size: Vec2,
+    rotation: f32,
+    pivot: Option<Vec2>,
+    mask: Option<&AlphaMask>,
+) -> bool {
+    let pivot = pivot.unwrap_or(position + size / 2.);
+
+    // Undo the rotation to get the point on the unrotated texture
+    let local = Vec2::from_angle(-rotation).rotate(point - pivot) + pivot - position;
+
+    let inside = 0. <= local.x && local.x <= size.x && 0. <= local.y && local.y <= size.y;
+    inside
+        && mask.is_none_or(|mask| {
+            let mask_size = Vec2::new(mask.width as f32, mask.height as f32);
+            let local = local * mask_size / size;
+            mask.is_opaque(local.x, local.y)
+        })
 }
 
 pub struct Button<'a> {
     pub x: f32,
     pub y: f32,
     pub rotation: f32,
+    /// Point the button is rotated around, the center of the texture if not set.
+    pub pivot: Option<Vec2>,
+    /// How much larger than its texture the button is drawn.
+    scale: f32,
 
     idle_texture: &'a Texture2D,
     hover_texture: &'a Texture2D,
     pressed_texture: &'a Texture2D,
+    mask: Option<&'a AlphaMask>,
 
     id: ButtonId,
 
     hovered: bool,
     pressed: bool,
+    highlighted: bool,
 }
 
 impl<'a> Button<'a> {
@@ -54,36 +155,92 @@ impl<'a> Button<'a> {
             x,
             y,
             rotation,
+            pivot: None,
+            scale: 1.,
             idle_texture,
             hover_texture,
             pressed_texture,
+            mask: None,
             id,
             pressed: false,
             hovered: false,
+            highlighted: false,
         }
     }
 
+    /// Only react to the pointer over the opaque parts of the texture.
+    pub fn with_mask(mut self, mask: &'a AlphaMask) -> Self {
+        self.mask = Some(mask);
+        self
+    }
+
+    /// Draw the button larger or smaller than its texture.
+    pub fn with_scale(mut self, scale: f32) -> Self {
+        self.scale = scale;
+        self
+    }
+
     pub fn id(&self) -> ButtonId {
         self.id
     }
 
-    pub fn draw(&self, render_hovered: bool) {
-        let texture = if self.hovered || render_hovered {
-            self.hover_texture
+    pub fn is_hovered(&self) -> bool {
+        self.hovered
+    }
+
+    /// Draw attention to the button with a pulsing hover texture, e.g. to show a hint.
+    pub fn set_highlighted(&mut self, highlighted: bool) {
+        self.highlighted = highlighted;
+    }
+
+    pub fn draw(&self, render_hovered: bool, renderer: Renderer) {
+        let state = if self.hovered || render_hovered {
+            ButtonState::Hovered
         } else if self.pressed {
-            self.pressed_texture
+            ButtonState::Pressed
         } else {
-            self.idle_texture
+            ButtonState::Idle
+        };
+
+        self.draw_state(state, 1.0, renderer);
+
+        if self.highlighted && !self.hovered {
+            const PULSE_FREQUENCY: f64 = 6.0;
+            let alpha = (0.5 + 0.5 * (get_time() * PULSE_FREQUENCY).sin()) as f32;
+            self.draw_state(ButtonState::Hovered, alpha, renderer);
+        }
+    }
+
+    fn draw_state(&self, state: ButtonState, alpha: f32, renderer: Renderer) {
+        if renderer == Renderer::Vector {
+            let position = Vec2::new(self.x, self.y);
+            vector::draw_button(
+                self.id,
+                position,
+                self.rotation,
+                self.pivot,
+                self.scale,
+                state,
+                alpha,
+            );
+            return;
+        }
+
+        let texture = match state {
+            ButtonState::Idle => self.idle_texture,
+            ButtonState::Hovered => self.hover_texture,
+            ButtonState::Pressed => self.pressed_texture,
         };
 
         draw_texture_ex(
             texture,
             self.x,
             self.y,
-            WHITE,
+            Color::new(1.0, 1.0, 1.0, alpha),
             DrawTextureParams {
+                dest_size: Some(texture.size() * self.scale),
                 rotation: self.rotation,
-                pivot: None,
+                pivot: self.pivot,
                 ..Default::default()
             },
         );
@@ -92,14 +249,16 @@ impl<'a> Button<'a> {
     pub fn handle_event(&mut self, event: &MouseEvent, new_events: &mut Vec<ButtonEvent>) {
         use MouseEvent::*;
 
-        let (width, height) = self.idle_texture.size().into();
-
         match *event {
             Moved { x, y } => {
-                let x_inside = self.x <= x && x <= self.x + width;
-                let y_inside = self.y <= y && y <= self.y + height;
-
-                self.hovered = x_inside && y_inside;
+                self.hovered = hit_test(
+                    Vec2::new(x, y),
+                    Vec2::new(self.x, self.y),
+                    self.idle_texture.size() * self.scale,
+                    self.rotation,
+                    self.pivot,
+                    self.mask,
+                );
 
                 if !self.hovered {
                     self.pressed = false;
@@ -112,7 +271,101 @@ impl<'a> Button<'a> {
                 self.pressed = true;
             }
             Released => self.pressed = false,
+            Exited => {
+                self.hovered = false;
+                self.pressed = false;
+            }
             _ => (),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use macroquad::color::WHITE;
+    use std::f32::consts::{FRAC_PI_2, FRAC_PI_4, PI};
+    use test_case::test_case;
+
+    const POSITION: Vec2 = Vec2::new(100., 100.);
+    const SIZE: Vec2 = Vec2::new(40., 20.);
+
+    fn hits(x: f32, y: f32, rotation: f32, pivot: Option<Vec2>) -> bool {
+        hit_test(Vec2::new(x, y), POSITION, SIZE, rotation, pivot, None)
+    }
+
+    /// A mask with only the left half of the texture opaque.
+    fn left_half_mask() -> AlphaMask {
+        let mut image = Image::gen_image_color(40, 20, Color::new(1., 1., 1., 0.));
+        for x in 0..20 {
+            for y in 0..20 {
+                image.set_pixel(x, y, WHITE);
+            }
+        }
+        AlphaMask::from_image(&image)
+    }
+
+    #[test_case(105., 105., true; "inside")]
+    #[test_case(139., 119., true; "near the far corner")]
+    #[test_case(145., 105., false; "right of the texture")]
+    #[test_case(105., 125., false; "below the texture")]
+    fn unrotated_button_is_hit_inside_its_texture(x: f32, y: f32, expected: bool) {
+        assert_eq!(hits(x, y, 0., None), expected);
+    }
+
+    // Rotated by a quarter turn around its center, the texture covers
+    // 110..130 horizontally and 90..130 vertically
+    #[test_case(115., 92., true; "top of the rotated texture")]
+    #[test_case(125., 128., true; "bottom of the rotated texture")]
+    #[test_case(103., 110., false; "where the unrotated texture was")]
+    #[test_case(135., 110., false; "right of the rotated texture")]
+    fn rotated_button_is_hit_where_it_is_drawn(x: f32, y: f32, expected: bool) {
+        assert_eq!(hits(x, y, FRAC_PI_2, None), expected);
+        assert_eq!(hits(x, y, -FRAC_PI_2, None), expected);
+    }
+
+    #[test_case(105., 105., true; "inside")]
+    #[test_case(101., 119., false; "cut off corner")]
+    fn diagonal_button_is_hit_where_it_is_drawn(x: f32, y: f32, expected: bool) {
+        let square = Vec2::new(20., 20.);
+
+        assert_eq!(
+            hit_test(Vec2::new(x, y), POSITION, square, FRAC_PI_4, None, None),
+            expected
+        );
+    }
+
+    #[test]
+    fn button_rotates_around_its_pivot() {
+        // Half a turn around the top left corner moves it up and to the left
+        let pivot = Some(POSITION);
+
+        assert!(hits(70., 90., PI, pivot));
+        assert!(!hits(110., 110., PI, pivot));
+    }
+
+    #[test_case(105., 110., 0., true; "opaque half")]
+    #[test_case(135., 110., 0., false; "transparent half")]
+    #[test_case(135., 110., PI, true; "opaque half turned around")]
+    fn mask_limits_the_hit_area(x: f32, y: f32, rotation: f32, expected: bool) {
+        let mask = left_half_mask();
+
+        assert_eq!(
+            hit_test(Vec2::new(x, y), POSITION, SIZE, rotation, None, Some(&mask)),
+            expected
+        );
+    }
+
+    // Drawn at half the size, the texture covers 100..120 horizontally
+    #[test_case(105., 105., true; "opaque half")]
+    #[test_case(115., 105., false; "transparent half")]
+    #[test_case(125., 105., false; "outside of the button")]
+    fn mask_is_scaled_with_the_button(x: f32, y: f32, expected: bool) {
+        let mask = left_half_mask();
+
+        assert_eq!(
+            hit_test(Vec2::new(x, y), POSITION, SIZE / 2., 0., None, Some(&mask)),
+            expected
+        );
+    }
+}
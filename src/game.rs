@@ -1,123 +1,220 @@
-use macroquad::prelude::*;
-use miniquad::date::now;
-use rand::rand;
-
 use crate::{
     button::{ButtonEvent, ButtonId, MouseEvent},
-    initialization::Assets,
+    gesture::{self, Drag},
+    history::MoveHistory,
+    initialization::{piece_location, piece_size, Assets},
+    keyboard::{hotkey_manipulation, KeyAction, KeyMap, Selection},
+    menu::{Menu, MenuEvent, MENU_KEY},
+    pointer::{self, PointerId},
+    scramble::{generate_shuffle_manipulations, random_state_builder, SequenceBuilder},
+    settings::{Renderer, Scramble, Settings, Volume},
     solved_marker::SolvedMarker,
-    Button, Manipulation, PieceCollection,
+    solver::HintSearch,
+    statistics::{Solve, Statistics},
+    storage::Storage,
+    vector,
+    viewport::Viewport,
+    BoardSize, BoardState, Button, Easing, Manipulation, PieceCollection, Position,
+};
+use macroquad::{
+    audio::{self, PlaySoundParams, Sound},
+    prelude::*,
 };
+use miniquad::date::now;
+use std::collections::HashSet;
+
+/// How the main loop of a game ended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GameEnd {
+    Solved,
+    /// The player asked for a new game before solving this one.
+    Abandoned,
+}
 
 pub struct Game<'a> {
     pub assets: &'a Assets,
     pub buttons: Vec<Button<'a>>,
     pub solved_markers: Vec<SolvedMarker<'a>>,
     pub pieces: PieceCollection<'a>,
+
+    seed: u64,
+    hints: usize,
+    start_time: Option<f64>,
+    finish_time: Option<f64>,
+    history: MoveHistory,
+    /// Boards the player has been at, which hints don't lead back to.
+    visited: HashSet<BoardState>,
+    hint_search: HintSearch,
+    key_map: KeyMap,
+    selection: Selection,
+    selection_visible: bool,
+    /// The drag being made and the pointer making it.
+    drag: Option<(PointerId, Drag)>,
+    settings: Settings,
+    menu: Option<Menu>,
+    is_abandoned: bool,
+    storage: Box<dyn Storage>,
 }
 
-fn broadcast_input_events(buttons: &mut [Button], new_events: &mut Vec<ButtonEvent>) {
-    let (x, y) = mouse_position();
-    let mut events = vec![];
+const UNDO_KEY: KeyCode = KeyCode::Z;
 
-    if mouse_delta_position() != Vec2::ZERO {
-        events.push(MouseEvent::Moved { x, y });
-    }
+/// Easing of the scramble at the start, which is independent of the
+/// easing the player chooses for their moves.
+const SHUFFLE_EASING: Easing = Easing::Quad;
 
-    if is_mouse_button_pressed(MouseButton::Left) {
-        events.push(MouseEvent::Pressed { x, y });
-    }
+/// Easing of the rings spinning after a win, linear so that the spin doesn't
+/// stop between the rotations.
+const VICTORY_EASING: Easing = Easing::Linear;
 
-    if is_mouse_button_released(MouseButton::Left) {
-        events.push(MouseEvent::Released);
-    }
+/// Time spent building a random state scramble every frame, in seconds.
+const SCRAMBLE_TIME_PER_FRAME: f64 = 0.01;
+
+/// Length of the scramble that stands in for a random state when the rules
+/// don't allow every manipulation.
+const RESTRICTED_SCRAMBLE_MOVES: usize = 50;
+
+/// How much searching for a shortest solution a hint is allowed before
+/// falling back to a plan that solves the board piece by piece.
+const HINT_MAX_NODES: u64 = 50_000;
 
+/// Time spent looking for a hint every frame, in seconds.
+const HINT_TIME_PER_FRAME: f64 = 0.005;
+
+fn broadcast_input_events(
+    buttons: &mut [Button],
+    events: &[MouseEvent],
+    new_events: &mut Vec<ButtonEvent>,
+) {
     for control in buttons.iter_mut() {
-        for event in &events {
+        for event in events {
             control.handle_event(event, new_events);
         }
     }
 }
 
-fn handle_events(new_events: &[ButtonEvent], pieces: &mut PieceCollection) {
-    const ANIMATION_LENGTH: f64 = 0.35;
-    for event in new_events.iter() {
-        match event {
-            ButtonEvent::Pressed(ButtonId::HorizontalRight(row)) => {
-                pieces.apply_manipulation(Manipulation::SlideRight(*row), ANIMATION_LENGTH)
-            }
-            ButtonEvent::Pressed(ButtonId::HorizontalLeft(row)) => {
-                pieces.apply_manipulation(Manipulation::SlideLeft(*row), ANIMATION_LENGTH)
-            }
-            ButtonEvent::Pressed(ButtonId::VerticalUp(col)) => {
-                pieces.apply_manipulation(Manipulation::SlideUp(*col), ANIMATION_LENGTH)
-            }
-            ButtonEvent::Pressed(ButtonId::VerticalDown(col)) => {
-                pieces.apply_manipulation(Manipulation::SlideDown(*col), ANIMATION_LENGTH)
-            }
-            ButtonEvent::Pressed(ButtonId::RotateClockwise(ring)) => {
-                pieces.apply_manipulation(Manipulation::RotateClockwise(*ring), ANIMATION_LENGTH)
-            }
-            ButtonEvent::Pressed(ButtonId::RotateAnticlockwise(ring)) => pieces
-                .apply_manipulation(Manipulation::RotateAnticlockwise(*ring), ANIMATION_LENGTH),
-        }
+/// Presses of the buttons that have keyboard shortcuts.
+fn shortcut_events() -> Vec<ButtonEvent> {
+    let mut new_events = vec![];
+
+    let control_down = [KeyCode::LeftControl, KeyCode::RightControl]
+        .into_iter()
+        .any(is_key_down);
+    let shift_down = [KeyCode::LeftShift, KeyCode::RightShift]
+        .into_iter()
+        .any(is_key_down);
+
+    if control_down && is_key_pressed(UNDO_KEY) {
+        let id = if shift_down {
+            ButtonId::Redo
+        } else {
+            ButtonId::Undo
+        };
+        new_events.push(ButtonEvent::Pressed(id));
     }
+
+    new_events
 }
 
-fn generate_shuffle_manipulations(count: usize) -> Vec<Manipulation> {
-    use Manipulation::*;
-
-    let all_manipulations = [
-        RotateClockwise(0),
-        RotateAnticlockwise(0),
-        RotateClockwise(1),
-        RotateAnticlockwise(1),
-        RotateClockwise(2),
-        RotateAnticlockwise(2),
-        SlideLeft(3),
-        SlideRight(3),
-        SlideLeft(4),
-        SlideRight(4),
-        SlideLeft(5),
-        SlideRight(5),
-        SlideUp(3),
-        SlideDown(3),
-        SlideUp(4),
-        SlideDown(4),
-        SlideUp(5),
-        SlideDown(5),
-    ];
-
-    let mut manipulations = vec![];
-    let mut last_index = None;
-
-    for _ in 0..count {
-        let mut index = rand() as usize % all_manipulations.len();
-
-        // Do not allow the same manipulation to happen twice or the two opposite
-        // manipulations to happen in one after the other
-        while last_index.is_some() && index / 2 == last_index.unwrap() / 2 {
-            index = rand() as usize % all_manipulations.len();
-        }
-
-        manipulations.push(all_manipulations[index]);
-        last_index = Some(index);
-    }
-
-    manipulations
+/// Format seconds as minutes, seconds and tenths of a second, e.g. `1:05.3`.
+fn format_time(seconds: f64) -> String {
+    let tenths = (seconds.max(0.0) * 10.0) as u64;
+    format!("{}:{:02}.{}", tenths / 600, tenths / 10 % 60, tenths % 10)
 }
 
 impl<'a> Game<'a> {
-    pub async fn run_shuffle(&mut self, shuffle_count: usize, animation_length: f64) {
-        let shuffle_manipulations = generate_shuffle_manipulations(shuffle_count);
+    pub fn new(
+        assets: &'a Assets,
+        buttons: Vec<Button<'a>>,
+        solved_markers: Vec<SolvedMarker<'a>>,
+        mut pieces: PieceCollection<'a>,
+        seed: u64,
+        settings: Settings,
+        storage: Box<dyn Storage>,
+    ) -> Self {
+        let size = pieces.state().size();
+        let ruleset = settings.ruleset.clone();
+
+        // Leave out the buttons the rules don't let the player use
+        let buttons = buttons
+            .into_iter()
+            .filter(|button| match button.id() {
+                ButtonId::Hint => ruleset.has_hints(),
+                id => id
+                    .manipulation()
+                    .is_none_or(|manipulation| ruleset.allows(manipulation)),
+            })
+            .collect();
+
+        pieces.set_ruleset(ruleset);
+        pieces.set_queue_moves(settings.queue_moves);
+        pieces.set_fast_mode(settings.fast_mode);
+
+        Self {
+            assets,
+            buttons,
+            solved_markers,
+            pieces,
+            seed,
+            hints: 0,
+            start_time: None,
+            finish_time: None,
+            history: MoveHistory::new(),
+            visited: HashSet::new(),
+            hint_search: HintSearch::new(HINT_MAX_NODES),
+            key_map: KeyMap::load(storage.as_ref()),
+            selection: Selection::new(size),
+            selection_visible: false,
+            drag: None,
+            settings,
+            menu: None,
+            is_abandoned: false,
+            storage,
+        }
+    }
+
+    /// The storage the game keeps its settings in.
+    pub fn storage_mut(&mut self) -> &mut dyn Storage {
+        self.storage.as_mut()
+    }
+
+    /// The settings as the player left them and the storage, for the next game.
+    pub fn into_settings(self) -> (Settings, Box<dyn Storage>) {
+        (self.settings, self.storage)
+    }
+
+    pub async fn run_shuffle(&mut self, animation_length: f64, max_total_length: f64) {
+        let ruleset = self.pieces.ruleset();
+
+        // Random states need every manipulation to be reached
+        let shuffle_manipulations = match self.settings.scramble {
+            Scramble::RandomState if ruleset.allows_all() => {
+                let builder = random_state_builder(self.seed, self.size());
+                self.build_scramble(builder).await
+            }
+            Scramble::RandomState => generate_shuffle_manipulations(
+                self.seed,
+                RESTRICTED_SCRAMBLE_MOVES,
+                self.size(),
+                ruleset,
+            ),
+            Scramble::Moves(count) => {
+                generate_shuffle_manipulations(self.seed, count, self.size(), ruleset)
+            }
+        };
+
+        // Long scrambles are sped up so that they don't keep the player waiting
+        let animation_length =
+            animation_length.min(max_total_length / shuffle_manipulations.len().max(1) as f64);
         let mut shuffle_manipulations = shuffle_manipulations.into_iter();
 
         loop {
             if !self.pieces.is_animating() {
                 match shuffle_manipulations.next() {
-                    Some(manipulation) => self
-                        .pieces
-                        .apply_manipulation(manipulation, animation_length),
+                    Some(manipulation) => self.pieces.apply_manipulation(
+                        manipulation,
+                        animation_length,
+                        SHUFFLE_EASING,
+                    ),
                     None => break,
                 }
             }
@@ -128,43 +225,128 @@ impl<'a> Game<'a> {
         }
     }
 
-    pub async fn run_main_loop(&mut self) {
+    /// Build the manipulations a part at a time, drawing the board in between
+    /// so that the game doesn't freeze while large boards are built.
+    async fn build_scramble(&self, mut builder: SequenceBuilder) -> Vec<Manipulation> {
+        loop {
+            let deadline = now() + SCRAMBLE_TIME_PER_FRAME;
+
+            while now() < deadline {
+                if builder.step() {
+                    return builder.manipulations();
+                }
+            }
+
+            self.draw_all(false, false);
+            next_frame().await
+        }
+    }
+
+    pub async fn run_main_loop(&mut self) -> GameEnd {
         loop {
-            let mut new_events = vec![];
-            broadcast_input_events(&mut self.buttons, &mut new_events);
+            let pointer_events = pointer::events();
             self.pieces.update();
 
-            handle_events(&new_events, &mut self.pieces);
+            if self.menu.is_some() {
+                let events: Vec<_> = pointer_events
+                    .into_iter()
+                    .flat_map(|(_, events)| events)
+                    .collect();
+                self.handle_menu(&events);
+            } else {
+                for (pointer, events) in pointer_events {
+                    // Other pointers wait until the drag is over, because the
+                    // dragged pieces would get out of sync with their moves
+                    if self
+                        .drag
+                        .as_ref()
+                        .is_some_and(|(owner, _)| *owner != pointer)
+                    {
+                        continue;
+                    }
+
+                    let mut new_events = vec![];
+                    broadcast_input_events(&mut self.buttons, &events, &mut new_events);
+                    self.handle_events(&new_events);
+                    self.handle_drag(pointer, &events);
+                }
+
+                self.handle_events(&shortcut_events());
+                self.handle_keys();
+            }
+
+            self.update_hint();
 
             self.draw_all(false, true);
 
+            if let Some(menu) = &self.menu {
+                menu.draw(&self.settings, &self.key_map);
+            }
+
+            if self.is_abandoned {
+                return GameEnd::Abandoned;
+            }
+
             if self.pieces.is_solved() && !self.pieces.is_animating() {
-                break;
+                self.finish_time = Some(now());
+                self.selection_visible = false;
+                return GameEnd::Solved;
             }
             next_frame().await
         }
     }
 
-    pub async fn run_victory_loop(&mut self) {
+    /// The result of the game, once the main loop is over.
+    pub fn solve(&self) -> Solve {
+        Solve {
+            seed: self.seed,
+            moves: self.history.move_count(),
+            time: self.elapsed_time(),
+            hints: self.hints,
+            date: now(),
+            size: self.size(),
+            ruleset: self.pieces.ruleset().clone(),
+        }
+    }
+
+    /// Celebrate the win until the player clicks, taps or presses a key for a
+    /// new game.
+    pub async fn run_victory_loop(&mut self, statistics: &Statistics) {
         use Manipulation::*;
 
+        self.play_sound(&self.assets.sound_solved);
+
         // Make sure the buttons don't stay hovered
         for button in self.buttons.iter_mut() {
-            button.handle_event(&MouseEvent::Moved { x: 0.0, y: 0.0 }, &mut vec![]);
+            button.handle_event(&MouseEvent::Exited, &mut vec![]);
         }
 
         loop {
             if !self.pieces.is_animating() {
-                self.pieces.apply_manipulation(RotateClockwise(0), 1.0);
-                self.pieces.apply_manipulation(RotateClockwise(1), 1.0);
-                self.pieces.apply_manipulation(RotateClockwise(2), 1.0);
+                for ring in self.size().rings() {
+                    self.pieces
+                        .apply_manipulation(RotateClockwise(ring), 1.0, VICTORY_EASING);
+                }
             }
 
             self.pieces.update();
             self.draw_all(false, true);
 
             // Additionally draw the victory marker
-            draw_texture(&self.assets.img_victory_marker, 0., 0., WHITE);
+            match self.settings.renderer {
+                Renderer::Bitmap => draw_texture(&self.assets.img_victory_marker, 0., 0., WHITE),
+                Renderer::Vector => vector::draw_victory_marker(),
+            }
+            self.draw_victory_stats(statistics);
+
+            let pressed = pointer::events()
+                .into_iter()
+                .flat_map(|(_, events)| events)
+                .any(|event| matches!(event, MouseEvent::Pressed { .. }));
+
+            if pressed || get_last_key_pressed().is_some() {
+                return;
+            }
 
             next_frame().await
         }
@@ -188,16 +370,466 @@ impl<'a> Game<'a> {
         }
     }
 
+    fn handle_events(&mut self, new_events: &[ButtonEvent]) {
+        for event in new_events.iter() {
+            let ButtonEvent::Pressed(id) = event;
+
+            match id {
+                ButtonId::Hint => self.show_hint(),
+                ButtonId::Undo => self.undo(),
+                ButtonId::Redo => self.redo(),
+                ButtonId::Settings => self.open_menu(),
+                _ => {
+                    if let Some(manipulation) = id.manipulation() {
+                        self.play(manipulation);
+                    }
+                }
+            }
+        }
+    }
+
+    fn handle_drag(&mut self, pointer: PointerId, events: &[MouseEvent]) {
+        for event in events {
+            match *event {
+                MouseEvent::Pressed { x, y } if self.drag.is_none() => {
+                    self.start_drag(pointer, x, y)
+                }
+                MouseEvent::Moved { x, y } => {
+                    // The keyboard selection is only shown while the keyboard is used
+                    self.selection_visible = false;
+
+                    if let Some((_, drag)) = self.drag.as_mut() {
+                        drag.update(x, y);
+                        let manipulation = drag
+                            .manipulation()
+                            .filter(|manipulation| self.pieces.ruleset().allows(*manipulation));
+
+                        if let Some(manipulation) = manipulation {
+                            self.pieces
+                                .preview_manipulation(manipulation, drag.progress());
+                        }
+                    }
+                }
+                MouseEvent::Released | MouseEvent::Exited => self.finish_drag(),
+                _ => (),
+            }
+        }
+    }
+
+    fn start_drag(&mut self, pointer: PointerId, x: f32, y: f32) {
+        let over_button = self.buttons.iter().any(|button| button.is_hovered());
+
+        if !over_button && !self.pieces.is_animating() {
+            self.drag = gesture::position_at(x, y, self.size())
+                .map(|position| (pointer, Drag::new(position, x, y)));
+        }
+    }
+
+    fn finish_drag(&mut self) {
+        let Some((_, drag)) = self.drag.take() else {
+            return;
+        };
+
+        let path_pos = drag.progress();
+
+        let manipulation = drag
+            .manipulation()
+            .filter(|manipulation| self.pieces.ruleset().allows(*manipulation));
+
+        match manipulation {
+            Some(manipulation) if drag.is_committed() => self.play_from(manipulation, path_pos),
+            Some(manipulation) => self.pieces.cancel_preview(
+                manipulation,
+                path_pos,
+                self.animation_length() * path_pos,
+                self.settings.easing,
+            ),
+            None => (),
+        }
+    }
+
+    fn handle_keys(&mut self) {
+        // The dragged pieces would get out of sync with the drag
+        if self.drag.is_some() {
+            return;
+        }
+
+        // Modified keys are shortcuts like undo, not bindings
+        let modifier_down = [
+            KeyCode::LeftControl,
+            KeyCode::RightControl,
+            KeyCode::LeftAlt,
+            KeyCode::RightAlt,
+        ]
+        .into_iter()
+        .any(is_key_down);
+
+        if modifier_down {
+            return;
+        }
+
+        for key in get_keys_pressed() {
+            if key == MENU_KEY {
+                self.open_menu();
+                return;
+            }
+
+            let Some(action) = self.key_map.action(key) else {
+                continue;
+            };
+
+            let size = self.size();
+            let manipulation = match action {
+                KeyAction::Manipulation(manipulation) => {
+                    let shift_down = [KeyCode::LeftShift, KeyCode::RightShift]
+                        .into_iter()
+                        .any(is_key_down);
+                    hotkey_manipulation(manipulation, size, shift_down)
+                }
+                KeyAction::Cursor(direction) => {
+                    let manipulation = self.selection.manipulation(direction);
+                    if manipulation.is_none() {
+                        self.selection = self.selection.moved(direction, size);
+                    }
+                    self.selection_visible = true;
+                    manipulation
+                }
+                KeyAction::RotateClockwise => self.selection.rotation(true),
+                KeyAction::RotateAnticlockwise => self.selection.rotation(false),
+                KeyAction::NextSelection => {
+                    self.selection = self.selection.next(size);
+                    self.selection_visible = true;
+                    None
+                }
+                KeyAction::Hint => {
+                    self.show_hint();
+                    None
+                }
+                KeyAction::ToggleColorblindMode => {
+                    self.settings.colorblind_mode = !self.settings.colorblind_mode;
+                    self.settings_changed();
+                    None
+                }
+                KeyAction::ToggleRenderer => {
+                    self.settings.renderer = self.settings.renderer.toggled();
+                    self.settings_changed();
+                    None
+                }
+            };
+
+            if let Some(manipulation) = manipulation {
+                self.play(manipulation);
+            }
+        }
+    }
+
+    /// Apply the settings that aren't read when they are needed and save them.
+    fn settings_changed(&mut self) {
+        self.pieces.set_queue_moves(self.settings.queue_moves);
+        self.pieces.set_fast_mode(self.settings.fast_mode);
+
+        if let Err(error) = self.settings.save(self.storage.as_mut()) {
+            println!("Failed to save settings: {}", error);
+        }
+    }
+
+    fn save_key_map(&mut self) {
+        if let Err(error) = self.key_map.save(self.storage.as_mut()) {
+            println!("Failed to save key bindings: {}", error);
+        }
+    }
+
+    /// Size of the board being played, which stays the same for the whole game.
+    fn size(&self) -> BoardSize {
+        self.pieces.state().size()
+    }
+
+    /// Time it takes to animate a whole manipulation made by the player.
+    fn animation_length(&self) -> f64 {
+        self.settings.animation_speed.animation_length()
+    }
+
+    fn open_menu(&mut self) {
+        // Make sure the buttons don't stay hovered under the menu
+        for button in self.buttons.iter_mut() {
+            button.handle_event(&MouseEvent::Exited, &mut vec![]);
+        }
+
+        self.menu = Some(Menu::new(&self.settings));
+    }
+
+    fn handle_menu(&mut self, pointer_events: &[MouseEvent]) {
+        let Some(menu) = self.menu.as_mut() else {
+            return;
+        };
+
+        let mut menu_events: Vec<_> = pointer_events
+            .iter()
+            .filter_map(|event| menu.handle_event(event, &mut self.settings, &mut self.key_map))
+            .collect();
+        menu_events.extend(
+            get_keys_pressed()
+                .into_iter()
+                .filter_map(|key| menu.handle_key(key, &mut self.key_map)),
+        );
+
+        for event in menu_events {
+            match event {
+                MenuEvent::SettingsChanged => self.settings_changed(),
+                MenuEvent::KeyMapChanged => self.save_key_map(),
+                MenuEvent::NewGame => self.is_abandoned = true,
+                MenuEvent::Closed => self.menu = None,
+            }
+        }
+    }
+
+    /// Make a move chosen by the player.
+    fn play(&mut self, manipulation: Manipulation) {
+        self.play_from(manipulation, 0.0);
+    }
+
+    /// Same as [Self::play], but for a move the pieces are already a part of
+    /// the way through, e.g. because they were dragged.
+    fn play_from(&mut self, manipulation: Manipulation, path_pos: f64) {
+        if !self.pieces.ruleset().allows(manipulation) {
+            return;
+        }
+
+        self.history.play(manipulation);
+        self.make_move_from(manipulation, path_pos);
+    }
+
+    fn undo(&mut self) {
+        if let Some(manipulation) = self.history.undo() {
+            self.make_move(manipulation);
+        }
+    }
+
+    fn redo(&mut self) {
+        if let Some(manipulation) = self.history.redo() {
+            self.make_move(manipulation);
+        }
+    }
+
+    fn make_move(&mut self, manipulation: Manipulation) {
+        self.make_move_from(manipulation, 0.0);
+    }
+
+    fn make_move_from(&mut self, manipulation: Manipulation, path_pos: f64) {
+        self.play_sound(&self.assets.sound_move);
+        self.visited.insert(self.pieces.state().clone());
+        self.pieces.apply_manipulation_from(
+            manipulation,
+            path_pos,
+            self.animation_length() * (1.0 - path_pos),
+            self.settings.easing,
+        );
+        self.start_time.get_or_insert_with(now);
+
+        for button in self.buttons.iter_mut() {
+            button.set_highlighted(false);
+        }
+    }
+
+    fn play_sound(&self, sound: &Sound) {
+        if self.settings.volume != Volume::OFF {
+            let params = PlaySoundParams {
+                looped: false,
+                volume: self.settings.volume.gain(),
+            };
+            audio::play_sound(sound, params);
+        }
+    }
+
+    /// Start looking for a hint, which is shown by [Self::update_hint] once
+    /// it's found.
+    fn show_hint(&mut self) {
+        if self.pieces.ruleset().has_hints() {
+            self.hint_search.start(self.pieces.state(), &self.visited);
+        }
+    }
+
+    fn update_hint(&mut self) {
+        let hint = self
+            .hint_search
+            .poll(self.pieces.state(), &self.visited, HINT_TIME_PER_FRAME);
+
+        let Some(hint) = hint else {
+            return;
+        };
+
+        self.hints += 1;
+
+        for button in self.buttons.iter_mut() {
+            let is_hint = button.id().manipulation() == Some(hint);
+            button.set_highlighted(is_hint);
+        }
+    }
+
+    fn draw_selection(&self) {
+        const MARGIN: f32 = 2.;
+        const THICKNESS: f32 = 3.;
+
+        let size = self.size();
+        let piece_size = piece_size(size);
+
+        for position in Position::all(size).filter(|position| self.selection.contains(position)) {
+            let (x, y) = piece_location(size, position.row() as i32, position.col() as i32);
+
+            draw_rectangle_lines(
+                x - MARGIN,
+                y - MARGIN,
+                piece_size + 2. * MARGIN,
+                piece_size + 2. * MARGIN,
+                THICKNESS,
+                color_u8!(0xff, 0xc1, 0x45, 0xff),
+            );
+        }
+    }
+
+    fn draw_seed(&self) {
+        const FONT_SIZE: u16 = 18;
+        // Left of the settings button
+        const RIGHT_X: f32 = 452.;
+        const TOP_Y: f32 = 5.;
+
+        let text = format!("Seed {}", self.seed);
+        let dimensions = measure_text(&text, None, FONT_SIZE, 1.0);
+
+        draw_text(
+            &text,
+            RIGHT_X - dimensions.width,
+            TOP_Y + dimensions.offset_y,
+            FONT_SIZE as f32,
+            color_u8!(0x21, 0x21, 0x21, 0xff),
+        );
+    }
+
+    /// Time since the first move, stopped when the board is solved.
+    fn elapsed_time(&self) -> f64 {
+        match self.start_time {
+            Some(start) => self.finish_time.unwrap_or_else(now) - start,
+            None => 0.0,
+        }
+    }
+
+    fn draw_hud(&self) {
+        const FONT_SIZE: u16 = 22;
+        const LEFT_X: f32 = 50.;
+        const RIGHT_X: f32 = 450.;
+        const BASELINE_Y: f32 = 485.;
+        let color = color_u8!(0x21, 0x21, 0x21, 0xff);
+
+        let moves = format!("Moves {}", self.history.move_count());
+        draw_text(&moves, LEFT_X, BASELINE_Y, FONT_SIZE as f32, color);
+
+        let time = format_time(self.elapsed_time());
+        let dimensions = measure_text(&time, None, FONT_SIZE, 1.0);
+        draw_text(
+            &time,
+            RIGHT_X - dimensions.width,
+            BASELINE_Y,
+            FONT_SIZE as f32,
+            color,
+        );
+    }
+
+    /// Show the board to reach between the moves and the time, if the rules
+    /// want a specific one.
+    fn draw_target(&self) {
+        const CENTER_X: f32 = 250.;
+        const TOP_Y: f32 = 467.;
+        const HEIGHT: f32 = 30.;
+
+        if let Some(target) = self.pieces.target() {
+            let cell = HEIGHT / self.size().dimension() as f32;
+            vector::draw_target(target, CENTER_X, TOP_Y, cell);
+        }
+    }
+
+    fn draw_victory_stats(&self, statistics: &Statistics) {
+        const FONT_SIZE: f32 = 22.;
+        const CENTER_X: f32 = 250.;
+        const TOP_Y: f32 = 322.;
+        const PADDING: f32 = 8.;
+        const LINE_SPACING: f32 = 6.;
+
+        let format_optional_time = |time: Option<f64>| time.map_or("-".into(), format_time);
+
+        let lines = [
+            format!(
+                "Moves: {}  Time: {}  Hints: {}",
+                self.history.move_count(),
+                format_time(self.elapsed_time()),
+                self.hints
+            ),
+            format!(
+                "Best: {} / {} moves",
+                format_optional_time(statistics.best_time()),
+                statistics
+                    .best_moves()
+                    .map_or("-".into(), |moves| moves.to_string()),
+            ),
+            format!(
+                "Ao5: {}  Ao12: {}  Solves: {}",
+                format_optional_time(statistics.average_of(5)),
+                format_optional_time(statistics.average_of(12)),
+                statistics.solve_count()
+            ),
+            "Click or press a key for a new game".to_string(),
+        ];
+
+        let dimensions: Vec<_> = lines
+            .iter()
+            .map(|line| measure_text(line, None, FONT_SIZE as u16, 1.0))
+            .collect();
+
+        let line_height = FONT_SIZE + LINE_SPACING;
+        let text_width = dimensions.iter().map(|d| d.width).fold(0., f32::max);
+        let width = text_width + 2. * PADDING;
+        let height = lines.len() as f32 * line_height - LINE_SPACING + 2. * PADDING;
+        let x = CENTER_X - width / 2.;
+
+        draw_rectangle(x, TOP_Y, width, height, color_u8!(0x67, 0x67, 0x67, 0xff));
+        draw_rectangle_lines(
+            x,
+            TOP_Y,
+            width,
+            height,
+            3.,
+            color_u8!(0x21, 0x21, 0x21, 0xff),
+        );
+
+        for (i, (line, dimensions)) in lines.iter().zip(dimensions.iter()).enumerate() {
+            draw_text(
+                line,
+                CENTER_X - dimensions.width / 2.,
+                TOP_Y + PADDING + i as f32 * line_height + FONT_SIZE * 0.75,
+                FONT_SIZE,
+                color_u8!(0xff, 0xe6, 0x6d, 0xff),
+            );
+        }
+    }
+
     fn draw_all(&self, draw_buttons_as_hovered: bool, draw_solved_markers: bool) {
-        // Draw the background
-        draw_texture(&self.assets.img_board, 0., 0., WHITE);
+        Viewport::current().apply();
+
+        let renderer = self.settings.renderer;
+
+        // Draw the background. Its image only fits the standard board.
+        match renderer {
+            Renderer::Bitmap if self.size() == BoardSize::STANDARD => {
+                draw_texture(&self.assets.img_board, 0., 0., WHITE)
+            }
+            _ => vector::draw_board(self.size()),
+        }
 
         // Draw solved markers
         if draw_solved_markers {
             self.solved_markers
                 .iter()
                 .filter(|marker| self.pieces.is_square_solved(marker.square()))
-                .for_each(|marker| marker.draw());
+                .for_each(|marker| marker.draw(renderer));
         }
 
         // Draw the rotational buttons
@@ -209,14 +841,22 @@ impl<'a> Game<'a> {
         });
 
         for button in rotational_buttons {
-            button.draw(draw_buttons_as_hovered);
+            button.draw(draw_buttons_as_hovered, renderer);
         }
 
         // Draw the pieces
-        self.pieces.draw();
+        self.pieces.draw(self.settings.colorblind_mode, renderer);
+
+        // Draw the keyboard selection
+        if self.selection_visible {
+            self.draw_selection();
+        }
 
         // Draw surroundings and cover pieces outside the board
-        draw_texture(&self.assets.img_surroundings, 0., 0., WHITE);
+        match renderer {
+            Renderer::Bitmap => draw_texture(&self.assets.img_surroundings, 0., 0., WHITE),
+            Renderer::Vector => vector::draw_surroundings(),
+        }
 
         // Draw the linear buttons
         let linear_buttons = self.buttons.iter().filter(|button| {
@@ -227,7 +867,25 @@ impl<'a> Game<'a> {
         });
 
         for drawable in linear_buttons {
-            drawable.draw(draw_buttons_as_hovered);
+            drawable.draw(draw_buttons_as_hovered, renderer);
         }
+
+        self.draw_seed();
+        self.draw_hud();
+        self.draw_target();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_case::test_case;
+
+    #[test_case(0.0, "0:00.0")]
+    #[test_case(5.36, "0:05.3")]
+    #[test_case(65.3, "1:05.3")]
+    #[test_case(600.0, "10:00.0")]
+    fn format_time_returns_expected_value(seconds: f64, expected: &str) {
+        assert_eq!(format_time(seconds), expected);
     }
 }
@@ -0,0 +1,226 @@
+//! Recognition of drag gestures on the board.
+//!
+//! Dragging a piece along a middle row or column slides it, while dragging
+//! it along the ring it's on rotates the ring. The direction is decided once
+//! the drag is long enough and stays the same until it's released.
+
+use crate::{
+    initialization::{piece_location, piece_size, PIECE_DISTANCE},
+    pieces::position::Square,
+    BoardSize, Manipulation, Position,
+};
+
+/// How far the pointer has to move before the drag direction is decided.
+const LOCK_DISTANCE: f32 = 8.;
+/// How far the pointer has to move for a whole manipulation. Both slides and
+/// rotations move the pieces by a whole square, which is as wide on every
+/// board size.
+const MANIPULATION_DISTANCE: f32 = 3. * PIECE_DISTANCE;
+/// Fraction of a manipulation after which releasing the drag makes it.
+const COMMIT_PROGRESS: f64 = 0.3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Axis {
+    Horizontal,
+    Vertical,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Drag {
+    position: Position,
+    start_x: f32,
+    start_y: f32,
+    axis: Option<Axis>,
+    manipulation: Option<Manipulation>,
+    progress: f64,
+}
+
+/// The position of the piece under the point on a board of the size, if any.
+pub fn position_at(x: f32, y: f32, size: BoardSize) -> Option<Position> {
+    let piece_size = piece_size(size);
+
+    Position::all(size).find(|position| {
+        let (left, top) = piece_location(size, position.row() as i32, position.col() as i32);
+        (left..=left + piece_size).contains(&x) && (top..=top + piece_size).contains(&y)
+    })
+}
+
+/// The manipulation made by dragging the piece at the position along the
+/// axis, toward larger coordinates if `forward` is set.
+fn drag_manipulation(position: &Position, axis: Axis, forward: bool) -> Option<Manipulation> {
+    use Manipulation::*;
+
+    let is_middle = |line: u8| position.size().middle_lines().contains(&line);
+
+    match axis {
+        Axis::Horizontal if is_middle(position.row()) => {
+            let constructor = if forward { SlideRight } else { SlideLeft };
+            Some(constructor(position.row()))
+        }
+        Axis::Vertical if is_middle(position.col()) => {
+            let constructor = if forward { SlideDown } else { SlideUp };
+            Some(constructor(position.col()))
+        }
+        _ => {
+            let ring = position.ring()?;
+
+            // Clockwise is rightward in the north square, downward in the
+            // east square and the opposite in the other two
+            let clockwise = match position.square() {
+                Square::North | Square::East => forward,
+                Square::South | Square::West => !forward,
+                Square::Center => return None,
+            };
+
+            let constructor = if clockwise {
+                RotateClockwise
+            } else {
+                RotateAnticlockwise
+            };
+            Some(constructor(ring))
+        }
+    }
+}
+
+impl Drag {
+    pub fn new(position: Position, x: f32, y: f32) -> Self {
+        Self {
+            position,
+            start_x: x,
+            start_y: y,
+            axis: None,
+            manipulation: None,
+            progress: 0.,
+        }
+    }
+
+    /// Follow the pointer to its new location.
+    pub fn update(&mut self, x: f32, y: f32) {
+        let dx = x - self.start_x;
+        let dy = y - self.start_y;
+
+        if self.axis.is_none() && dx.hypot(dy) >= LOCK_DISTANCE {
+            self.axis = Some(if dx.abs() >= dy.abs() {
+                Axis::Horizontal
+            } else {
+                Axis::Vertical
+            });
+        }
+
+        let Some(axis) = self.axis else {
+            return;
+        };
+
+        let distance = match axis {
+            Axis::Horizontal => dx,
+            Axis::Vertical => dy,
+        };
+
+        self.manipulation = drag_manipulation(&self.position, axis, distance >= 0.);
+        self.progress = (distance.abs() / MANIPULATION_DISTANCE).min(1.) as f64;
+    }
+
+    /// The manipulation the drag is heading toward, once its direction is known.
+    pub fn manipulation(&self) -> Option<Manipulation> {
+        self.manipulation
+    }
+
+    /// How much of the manipulation has been dragged, from 0 to 1.
+    pub fn progress(&self) -> f64 {
+        self.progress
+    }
+
+    /// Whether releasing the drag now should make the manipulation.
+    pub fn is_committed(&self) -> bool {
+        self.manipulation.is_some() && self.progress >= COMMIT_PROGRESS
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_case::test_case;
+    use Manipulation::*;
+
+    fn drag(row: u8, col: u8, dx: f32, dy: f32) -> Drag {
+        let position = Position::new(BoardSize::STANDARD, row, col).unwrap();
+        let mut drag = Drag::new(position, 100., 100.);
+        drag.update(100. + dx, 100. + dy);
+        drag
+    }
+
+    #[test_case(4, 1, -60., 5., SlideLeft(4); "left along a middle row")]
+    #[test_case(3, 4, 60., 0., SlideRight(3); "right through the center")]
+    #[test_case(1, 5, 3., -60., SlideUp(5); "up along a middle column")]
+    #[test_case(5, 5, 0., 60., SlideDown(5); "down through the center")]
+    #[test_case(1, 4, 60., 0., RotateClockwise(1); "right in the north square")]
+    #[test_case(4, 7, 0., 60., RotateClockwise(1); "down in the east square")]
+    #[test_case(8, 3, 60., 0., RotateAnticlockwise(2); "right in the south square")]
+    #[test_case(5, 0, 0., 60., RotateAnticlockwise(2); "down in the west square")]
+    #[test_case(2, 3, -60., 0., RotateAnticlockwise(0); "left in the north square")]
+    fn drag_makes_expected_manipulation(
+        row: u8,
+        col: u8,
+        dx: f32,
+        dy: f32,
+        expected: Manipulation,
+    ) {
+        assert_eq!(drag(row, col, dx, dy).manipulation(), Some(expected));
+    }
+
+    #[test]
+    fn short_drag_has_no_direction() {
+        let drag = drag(4, 4, 3., 3.);
+
+        assert_eq!(drag.manipulation(), None);
+        assert!(!drag.is_committed());
+    }
+
+    #[test]
+    fn drag_keeps_its_axis() {
+        let mut drag = drag(4, 1, 20., 0.);
+        drag.update(105., 160.);
+
+        assert_eq!(drag.manipulation(), Some(SlideRight(4)));
+    }
+
+    #[test]
+    fn reversed_drag_makes_the_inverse() {
+        let mut drag = drag(4, 1, 20., 0.);
+        drag.update(40., 100.);
+
+        assert_eq!(drag.manipulation(), Some(SlideLeft(4)));
+    }
+
+    #[test_case(20., 0.15, false)]
+    #[test_case(60., 0.44, true)]
+    #[test_case(500., 1., true)]
+    fn progress_follows_the_drag(distance: f32, expected: f64, committed: bool) {
+        let drag = drag(4, 4, distance, 0.);
+
+        assert!((drag.progress() - expected).abs() < 0.01);
+        assert_eq!(drag.is_committed(), committed);
+    }
+
+    #[test_case(BoardSize::STANDARD, 3, 7)]
+    #[test_case(BoardSize::new(2).unwrap(), 2, 5)]
+    #[test_case(BoardSize::new(5).unwrap(), 14, 6)]
+    fn position_is_found_under_the_point(size: BoardSize, row: u8, col: u8) {
+        let (x, y) = piece_location(size, row as i32, col as i32);
+
+        assert_eq!(
+            position_at(x + 10., y + 10., size),
+            Position::new(size, row, col).ok()
+        );
+        assert_eq!(position_at(5., 5., size), None);
+    }
+
+    #[test]
+    fn middle_lines_of_larger_boards_slide() {
+        let size = BoardSize::new(4).unwrap();
+        let mut drag = Drag::new(Position::new(size, 7, 0).unwrap(), 100., 100.);
+        drag.update(160., 100.);
+
+        assert_eq!(drag.manipulation(), Some(SlideRight(7)));
+    }
+}
@@ -0,0 +1,109 @@
+//! The moves of a game, kept apart from the pieces that show them so that
+//! undoing and redoing can be used without drawing anything.
+
+use crate::Manipulation;
+
+#[derive(Debug, Default)]
+pub struct MoveHistory {
+    done: Vec<Manipulation>,
+    undone: Vec<Manipulation>,
+    move_count: usize,
+}
+
+impl MoveHistory {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record a move chosen by the player. The moves that were undone can't
+    /// be redone after it.
+    pub fn play(&mut self, manipulation: Manipulation) {
+        self.done.push(manipulation);
+        self.undone.clear();
+        self.move_count += 1;
+    }
+
+    /// Take back the last move, returning the manipulation that does it.
+    pub fn undo(&mut self) -> Option<Manipulation> {
+        let manipulation = self.done.pop()?;
+        self.undone.push(manipulation);
+        self.move_count += 1;
+        Some(manipulation.inverse())
+    }
+
+    /// Make the last undone move again, returning its manipulation.
+    pub fn redo(&mut self) -> Option<Manipulation> {
+        let manipulation = self.undone.pop()?;
+        self.done.push(manipulation);
+        self.move_count += 1;
+        Some(manipulation)
+    }
+
+    /// Number of moves made, where undoing and redoing a move count as moves
+    /// too.
+    pub fn move_count(&self) -> usize {
+        self.move_count
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::BoardState;
+    use Manipulation::*;
+
+    fn apply(state: &mut BoardState, manipulation: Option<Manipulation>) {
+        state.apply(manipulation.unwrap()).unwrap();
+    }
+
+    #[test]
+    fn undo_then_redo_returns_the_same_board() {
+        let mut history = MoveHistory::new();
+        let mut state = BoardState::default();
+
+        for manipulation in [SlideLeft(3), RotateClockwise(1)] {
+            history.play(manipulation);
+            state.apply(manipulation).unwrap();
+        }
+        let played = state.clone();
+
+        apply(&mut state, history.undo());
+        apply(&mut state, history.undo());
+        assert_eq!(state, BoardState::default());
+        assert_eq!(history.undo(), None);
+
+        apply(&mut state, history.redo());
+        apply(&mut state, history.redo());
+        assert_eq!(state, played);
+        assert_eq!(history.redo(), None);
+    }
+
+    #[test]
+    fn new_move_clears_the_redo_history() {
+        let mut history = MoveHistory::new();
+
+        history.play(SlideUp(4));
+        history.undo();
+        history.play(SlideDown(5));
+
+        assert_eq!(history.redo(), None);
+        assert_eq!(history.undo(), Some(SlideUp(5)));
+        assert_eq!(history.undo(), None);
+    }
+
+    #[test]
+    fn undo_and_redo_count_as_moves() {
+        let mut history = MoveHistory::new();
+
+        history.play(RotateClockwise(0));
+        history.play(RotateAnticlockwise(2));
+        history.undo();
+        history.redo();
+
+        assert_eq!(history.move_count(), 4);
+
+        // Nothing to undo or redo isn't a move
+        history.redo();
+        assert_eq!(history.move_count(), 4);
+    }
+}
@@ -1,15 +1,20 @@
 use std::{
     collections::HashMap,
-    f32::consts::{FRAC_PI_2, PI},
+    f32::consts::{FRAC_PI_2, PI, SQRT_2},
 };
 
 use bezier_rs::Bezier;
-use macroquad::texture::Texture2D;
+use macroquad::audio::{load_sound_from_bytes, Sound};
+use macroquad::math::Vec2;
 use macroquad::prelude::ImageFormat;
+use macroquad::texture::{Image, Texture2D};
 
 use crate::{
-    button::ButtonId, pieces::position, solved_marker::SolvedMarker, Button, Path, Piece,
-    PieceCollection, Position, SubpathNoId,
+    button::{AlphaMask, ButtonId},
+    pieces::position,
+    solved_marker::SolvedMarker,
+    theme::Theme,
+    BoardSize, BoardState, Button, Color, Path, Piece, PieceCollection, Position, SubpathNoId,
 };
 
 #[non_exhaustive]
@@ -30,499 +35,520 @@ pub struct Assets {
     pub img_square_solved_center: Texture2D,
     pub img_square_solved_edges: Texture2D,
     pub img_victory_marker: Texture2D,
+    pub img_button_hint: Texture2D,
+    pub img_button_hint_hover: Texture2D,
+    pub img_button_hint_pressed: Texture2D,
+    pub img_button_undo: Texture2D,
+    pub img_button_undo_hover: Texture2D,
+    pub img_button_undo_pressed: Texture2D,
+    pub img_button_redo: Texture2D,
+    pub img_button_redo_hover: Texture2D,
+    pub img_button_redo_pressed: Texture2D,
+    pub img_button_settings: Texture2D,
+    pub img_button_settings_hover: Texture2D,
+    pub img_button_settings_pressed: Texture2D,
+    pub mask_arrow_linear: AlphaMask,
+    pub mask_arrow_rotational: AlphaMask,
+    pub sound_move: Sound,
+    pub sound_solved: Sound,
+}
+
+/// Load the image with the name from the theme, or the embedded one with
+/// the same name if the theme doesn't have it.
+macro_rules! load_image {
+    ($theme:expr, $name:expr) => {
+        $theme.image($name).cloned().unwrap_or_else(|| {
+            Image::from_file_with_format(
+                include_bytes!(concat!(
+                    env!("CARGO_MANIFEST_DIR"),
+                    "/assets/",
+                    $name,
+                    ".png"
+                )),
+                Some(ImageFormat::Png),
+            )
+            .unwrap()
+        })
+    };
 }
 
 macro_rules! load_texture {
-    ($file_name:expr) => {
-        Texture2D::from_file_with_format(
-            include_bytes!(concat!(env!("CARGO_MANIFEST_DIR"), "/assets/", $file_name,)),
-            Some(ImageFormat::Png),
-        )
+    ($theme:expr, $name:expr) => {
+        Texture2D::from_image(&load_image!($theme, $name))
+    };
+}
+
+/// Load the embedded sound with the name.
+macro_rules! load_sound {
+    ($name:expr) => {
+        load_sound_from_bytes(include_bytes!(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/assets/",
+            $name,
+            ".wav"
+        )))
+        .await
+        .unwrap()
+    };
+}
+
+macro_rules! load_mask {
+    ($theme:expr, $name:expr) => {
+        AlphaMask::from_image(&load_image!($theme, $name))
     };
 }
 
 #[rustfmt::skip]
-pub async fn load_assets() -> Assets {
+pub async fn load_assets(theme: &Theme) -> Assets {
     Assets {
-        img_board: load_texture!("board.png"),
-        img_surroundings: load_texture!("surroundings.png"),
-        img_arrow_linear: load_texture!("arrow_linear.png"),
-        img_arrow_linear_hover: load_texture!("arrow_linear_hover.png"),
-        img_arrow_linear_pressed: load_texture!("arrow_linear_pressed.png"),
-        img_arrow_rotational: load_texture!("arrow_rotational.png"),
-        img_arrow_rotational_hover: load_texture!("arrow_rotational_hover.png"),
-        img_arrow_rotational_pressed: load_texture!("arrow_rotational_pressed.png"),
-        img_piece_yellow: load_texture!("piece_yellow.png"),
-        img_piece_blue: load_texture!("piece_blue.png"),
-        img_piece_red: load_texture!("piece_red.png"),
-        img_piece_purple: load_texture!("piece_purple.png"),
-        img_piece_green: load_texture!("piece_green.png"),
-        img_square_solved_center: load_texture!("square_correct_center.png"),
-        img_square_solved_edges: load_texture!("square_correct_edges.png"),
-        img_victory_marker: load_texture!("victory_marker.png"),
+        img_board: load_texture!(theme, "board"),
+        img_surroundings: load_texture!(theme, "surroundings"),
+        img_arrow_linear: load_texture!(theme, "arrow_linear"),
+        img_arrow_linear_hover: load_texture!(theme, "arrow_linear_hover"),
+        img_arrow_linear_pressed: load_texture!(theme, "arrow_linear_pressed"),
+        img_arrow_rotational: load_texture!(theme, "arrow_rotational"),
+        img_arrow_rotational_hover: load_texture!(theme, "arrow_rotational_hover"),
+        img_arrow_rotational_pressed: load_texture!(theme, "arrow_rotational_pressed"),
+        img_piece_yellow: load_texture!(theme, "piece_yellow"),
+        img_piece_blue: load_texture!(theme, "piece_blue"),
+        img_piece_red: load_texture!(theme, "piece_red"),
+        img_piece_purple: load_texture!(theme, "piece_purple"),
+        img_piece_green: load_texture!(theme, "piece_green"),
+        img_square_solved_center: load_texture!(theme, "square_correct_center"),
+        img_square_solved_edges: load_texture!(theme, "square_correct_edges"),
+        img_victory_marker: load_texture!(theme, "victory_marker"),
+        img_button_hint: load_texture!(theme, "button_hint"),
+        img_button_hint_hover: load_texture!(theme, "button_hint_hover"),
+        img_button_hint_pressed: load_texture!(theme, "button_hint_pressed"),
+        img_button_undo: load_texture!(theme, "button_undo"),
+        img_button_undo_hover: load_texture!(theme, "button_undo_hover"),
+        img_button_undo_pressed: load_texture!(theme, "button_undo_pressed"),
+        img_button_redo: load_texture!(theme, "button_redo"),
+        img_button_redo_hover: load_texture!(theme, "button_redo_hover"),
+        img_button_redo_pressed: load_texture!(theme, "button_redo_pressed"),
+        img_button_settings: load_texture!(theme, "button_settings"),
+        img_button_settings_hover: load_texture!(theme, "button_settings_hover"),
+        img_button_settings_pressed: load_texture!(theme, "button_settings_pressed"),
+        mask_arrow_linear: load_mask!(theme, "arrow_linear"),
+        mask_arrow_rotational: load_mask!(theme, "arrow_rotational"),
+        sound_move: load_sound!("sound_move"),
+        sound_solved: load_sound!("sound_solved"),
     }
 }
 
-fn piece_location(row: i32, col: i32) -> (f32, f32) {
-    const TOP_LEFT_X: f32 = 50.200;
-    const TOP_LEFT_Y: f32 = 50.200;
-    const PIECE_DISTANCE: f32 = 45.237;
+/// Width and height of a piece on the standard board.
+pub const PIECE_SIZE: f32 = 38.;
+
+/// Distance between the top left corners of neighbouring pieces on the
+/// standard board.
+pub const PIECE_DISTANCE: f32 = 45.237;
 
-    (
-        TOP_LEFT_X + col as f32 * PIECE_DISTANCE,
-        TOP_LEFT_Y + row as f32 * PIECE_DISTANCE,
+/// Center of the middle piece of the standard board, which is the center of
+/// the board on every board size.
+const BOARD_MIDDLE: f32 = 50.2 + 4. * PIECE_DISTANCE + PIECE_SIZE / 2.;
+
+/// How much larger the pieces are than the ones of the standard board.
+pub fn piece_scale(size: BoardSize) -> f32 {
+    BoardSize::STANDARD.square_size() as f32 / size.square_size() as f32
+}
+
+/// Width and height of a piece.
+pub fn piece_size(size: BoardSize) -> f32 {
+    PIECE_SIZE * piece_scale(size)
+}
+
+/// Top left corner of the piece at the row and column. Locations outside the
+/// board are used by the pieces wrapping around it.
+pub fn piece_location(size: BoardSize, row: i32, col: i32) -> (f32, f32) {
+    let location = location_vec(size, row as f32, col as f32);
+    (location.x, location.y)
+}
+
+/// Same as [piece_location], but also between the pieces.
+fn location_vec(size: BoardSize, row: f32, col: f32) -> Vec2 {
+    let distance = PIECE_DISTANCE * piece_scale(size);
+    let middle = (size.dimension() - 1) as f32 / 2.;
+    let top_left = BOARD_MIDDLE - piece_size(size) / 2.;
+
+    Vec2::new(
+        top_left + (col - middle) * distance,
+        top_left + (row - middle) * distance,
     )
 }
 
-#[rustfmt::skip]
-pub fn initialize_buttons(assets: &Assets) -> Vec<Button> {
-    let new_linear_button = |x, y, rotation, id| {
+/// Scale of the arrow buttons, which shrink with the pieces on the larger
+/// boards, but don't grow on the smaller ones.
+fn arrow_scale(size: BoardSize) -> f32 {
+    piece_scale(size).min(1.)
+}
+
+pub fn initialize_buttons(assets: &Assets, size: BoardSize) -> Vec<Button<'_>> {
+    // Distance of the centers of the linear buttons from the edge of the
+    // window, without the part that depends on their size
+    const EDGE_MARGIN: f32 = 4.92;
+    // Distance of the centers of the rotational buttons from the middle of the
+    // sides of their ring, along both axes
+    const RING_MARGIN: f32 = 22.644;
+
+    let scale = arrow_scale(size);
+
+    // Buttons are rotated around their center, so they are placed by it
+    let new_linear_button = |center: Vec2, rotation, id| {
+        let top_left = center - assets.img_arrow_linear.size() * scale / 2.;
+
         Button::new(
             id,
             &assets.img_arrow_linear,
             &assets.img_arrow_linear_hover,
             &assets.img_arrow_linear_pressed,
-            x,
-            y,
+            top_left.x,
+            top_left.y,
             rotation,
         )
+        .with_mask(&assets.mask_arrow_linear)
+        .with_scale(scale)
     };
 
-    let new_rotational_button = |x, y, rotation, id| {
+    let new_rotational_button = |center: Vec2, rotation, id| {
+        let top_left = center - assets.img_arrow_rotational.size() * scale / 2.;
+
         Button::new(
             id,
             &assets.img_arrow_rotational,
             &assets.img_arrow_rotational_hover,
             &assets.img_arrow_rotational_pressed,
-            x,
-            y,
+            top_left.x,
+            top_left.y,
             rotation,
         )
+        .with_mask(&assets.mask_arrow_rotational)
+        .with_scale(scale)
     };
 
-    vec![
-        // Linear buttons
-        new_linear_button(186.646, 4.920, 0., ButtonId::VerticalUp(3)),
-        new_linear_button(231.884, 4.920, 0., ButtonId::VerticalUp(4)),
-        new_linear_button(277.122, 4.920, 0., ButtonId::VerticalUp(5)),
-        new_linear_button(186.646, 457.298, PI, ButtonId::VerticalDown(3)),
-        new_linear_button(231.884, 457.298, PI, ButtonId::VerticalDown(4)),
-        new_linear_button(277.122, 457.298, PI, ButtonId::VerticalDown(5)),
-        new_linear_button(4.920, 186.646, -FRAC_PI_2, ButtonId::HorizontalLeft(3)),
-        new_linear_button(4.920, 231.884, -FRAC_PI_2, ButtonId::HorizontalLeft(4)),
-        new_linear_button(4.920, 277.122, -FRAC_PI_2, ButtonId::HorizontalLeft(5)),
-        new_linear_button(457.298, 186.646, FRAC_PI_2, ButtonId::HorizontalRight(3)),
-        new_linear_button(457.298, 231.884, FRAC_PI_2, ButtonId::HorizontalRight(4)),
-        new_linear_button(457.298, 277.122, FRAC_PI_2, ButtonId::HorizontalRight(5)),
-        // Rotational buttons
-        new_rotational_button(85.462, 85.462, 0., ButtonId::RotateClockwise(2)),
-        new_rotational_button(117.330, 117.330, 0.,ButtonId::RotateClockwise(1)),
-        new_rotational_button(146.381, 146.381, 0., ButtonId::RotateClockwise(0)),
-        new_rotational_button(320.000, 320.000, PI, ButtonId::RotateClockwise(0)),
-        new_rotational_button(349.052, 349.052, PI, ButtonId::RotateClockwise(1)),
-        new_rotational_button(380.919, 380.919, PI, ButtonId::RotateClockwise(2)),
-        new_rotational_button(320.000, 146.381, -FRAC_PI_2, ButtonId::RotateAnticlockwise(0)),
-        new_rotational_button(349.052, 117.330, -FRAC_PI_2, ButtonId::RotateAnticlockwise(1)),
-        new_rotational_button(380.919, 85.462, -FRAC_PI_2, ButtonId::RotateAnticlockwise(2)),
-        new_rotational_button(146.381, 320.000, FRAC_PI_2, ButtonId::RotateAnticlockwise(0)),
-        new_rotational_button(117.330, 349.052, FRAC_PI_2, ButtonId::RotateAnticlockwise(1)),
-        new_rotational_button(85.462, 380.919, FRAC_PI_2, ButtonId::RotateAnticlockwise(2)),
-    ]
-}
+    let mut buttons = vec![];
+
+    let near_edge = EDGE_MARGIN + assets.img_arrow_linear.height() * scale / 2.;
+    let far_edge = 2. * BOARD_MIDDLE - near_edge;
+
+    for line in size.middle_lines() {
+        let line_center = location_vec(size, line as f32, line as f32) + piece_size(size) / 2.;
+        let along = line_center.x;
+
+        buttons.extend([
+            new_linear_button(Vec2::new(along, near_edge), 0., ButtonId::VerticalUp(line)),
+            new_linear_button(Vec2::new(along, far_edge), PI, ButtonId::VerticalDown(line)),
+            new_linear_button(
+                Vec2::new(near_edge, along),
+                -FRAC_PI_2,
+                ButtonId::HorizontalLeft(line),
+            ),
+            new_linear_button(
+                Vec2::new(far_edge, along),
+                FRAC_PI_2,
+                ButtonId::HorizontalRight(line),
+            ),
+        ]);
+    }
 
-pub type PathMap = HashMap<(Position, Position), Path>;
+    let middle = Vec2::splat(BOARD_MIDDLE);
+    let piece_distance = PIECE_DISTANCE * piece_scale(size);
+
+    for ring in size.rings() {
+        // The middle of the sides of the ring is this far from the middle of the board
+        let half_side = ((size.square_size() + 1) as f32 / 2. + ring as f32) * piece_distance;
+        let offset = half_side / SQRT_2 + RING_MARGIN;
+
+        let corner = |x: f32, y: f32| middle + Vec2::new(x, y) * offset;
+
+        buttons.extend([
+            new_rotational_button(corner(-1., -1.), 0., ButtonId::RotateClockwise(ring)),
+            new_rotational_button(corner(1., 1.), PI, ButtonId::RotateClockwise(ring)),
+            new_rotational_button(
+                corner(1., -1.),
+                -FRAC_PI_2,
+                ButtonId::RotateAnticlockwise(ring),
+            ),
+            new_rotational_button(
+                corner(-1., 1.),
+                FRAC_PI_2,
+                ButtonId::RotateAnticlockwise(ring),
+            ),
+        ]);
+    }
 
-pub fn initialize_paths() -> PathMap {
-    let mut map = HashMap::new();
+    buttons.extend([
+        Button::new(
+            ButtonId::Hint,
+            &assets.img_button_hint,
+            &assets.img_button_hint_hover,
+            &assets.img_button_hint_pressed,
+            4.920,
+            4.920,
+            0.,
+        ),
+        Button::new(
+            ButtonId::Undo,
+            &assets.img_button_undo,
+            &assets.img_button_undo_hover,
+            &assets.img_button_undo_pressed,
+            4.920,
+            457.298,
+            0.,
+        ),
+        Button::new(
+            ButtonId::Redo,
+            &assets.img_button_redo,
+            &assets.img_button_redo_hover,
+            &assets.img_button_redo_pressed,
+            457.298,
+            457.298,
+            0.,
+        ),
+        Button::new(
+            ButtonId::Settings,
+            &assets.img_button_settings,
+            &assets.img_button_settings_hover,
+            &assets.img_button_settings_pressed,
+            457.298,
+            4.920,
+            0.,
+        ),
+    ]);
+
+    buttons
+}
 
-    let linear_path_between = |row_from, col_from, row_to, col_to| {
-        let (x_from, y_from) = piece_location(row_from, col_from);
-        let (x_to, y_to) = piece_location(row_to, col_to);
+pub type PathMap = HashMap<(Position, Position), Path>;
 
-        SubpathNoId::from_bezier(&Bezier::from_linear_coordinates(
-            x_from as f64,
-            y_from as f64,
-            x_to as f64,
-            y_to as f64,
-        ))
-    };
+pub fn initialize_paths(size: BoardSize) -> PathMap {
+    use position::Manipulation::*;
 
-    // Horizontal
-    for row in 3..6 {
-        // Right, without ghosts
-        for col in 0..6 {
-            let col_to = col + 3;
-
-            let from = Position::new(row, col).unwrap();
-            let to = Position::new(row, col_to).unwrap();
-
-            map.insert(
-                (from, to),
-                Path {
-                    main_path: linear_path_between(
-                        row as i32,
-                        col as i32,
-                        row as i32,
-                        col_to as i32,
-                    ),
-                    ghost_path: None,
-                },
-            );
-        }
+    let mut map = HashMap::new();
 
-        // Right, with ghosts
-        for col in 6..9 {
-            let col_to = col + 3;
-
-            let from = Position::new(row, col).unwrap();
-            let to = Position::new(row, col_to % 9).unwrap();
-
-            map.insert(
-                (from, to),
-                Path {
-                    main_path: linear_path_between(
-                        row as i32,
-                        col as i32 - 9,
-                        row as i32,
-                        col_to as i32 % 9,
-                    ),
-                    ghost_path: Some(linear_path_between(
-                        row as i32,
-                        col as i32,
-                        row as i32,
-                        col_to as i32,
-                    )),
-                },
-            );
-        }
+    for from in Position::all(size) {
+        for manipulation in position::Manipulation::ALL {
+            let mut to = from;
+            if to.apply_manipulation(manipulation).is_err() {
+                continue;
+            }
 
-        // Left, without ghosts
-        for col in 3..9 {
-            let col_to = col - 3;
-
-            let from = Position::new(row, col).unwrap();
-            let to = Position::new(row, col_to).unwrap();
-
-            map.insert(
-                (from, to),
-                Path {
-                    main_path: linear_path_between(
-                        row as i32,
-                        col as i32,
-                        row as i32,
-                        col_to as i32,
-                    ),
-                    ghost_path: None,
-                },
-            );
-        }
+            let path = match manipulation {
+                RotateClockwise | RotateAnticlockwise => rotational_path(from, to),
+                SlideLeft | SlideRight | SlideUp | SlideDown => linear_path(from, to),
+            };
 
-        // Left, with ghosts
-        for col in 0..3 {
-            let col_to = col as i32 - 3;
-
-            let from = Position::new(row, col).unwrap();
-            let to = Position::new(row, (col_to + 9) as u8).unwrap();
-
-            map.insert(
-                (from, to),
-                Path {
-                    main_path: linear_path_between(
-                        row as i32,
-                        col as i32 + 9,
-                        row as i32,
-                        col_to + 9,
-                    ),
-                    ghost_path: Some(linear_path_between(
-                        row as i32, col as i32, row as i32, col_to,
-                    )),
-                },
-            );
+            map.insert((from, to), path);
         }
     }
 
-    // Vertical
-    for col in 3..6 {
-        // Down, without ghosts
-        for row in 0..6 {
-            let row_to = row + 3;
-
-            let from = Position::new(row, col).unwrap();
-            let to = Position::new(row_to, col).unwrap();
-
-            map.insert(
-                (from, to),
-                Path {
-                    main_path: linear_path_between(
-                        row as i32,
-                        col as i32,
-                        row_to as i32,
-                        col as i32,
-                    ),
-                    ghost_path: None,
-                },
-            );
-        }
-
-        // Down, with ghosts
-        for row in 6..9 {
-            let row_to = row + 3;
-
-            let from = Position::new(row, col).unwrap();
-            let to = Position::new(row_to % 9, col).unwrap();
-
-            map.insert(
-                (from, to),
-                Path {
-                    main_path: linear_path_between(
-                        row as i32 - 9,
-                        col as i32,
-                        row_to as i32 % 9,
-                        col as i32,
-                    ),
-                    ghost_path: Some(linear_path_between(
-                        row as i32,
-                        col as i32,
-                        row_to as i32,
-                        col as i32,
-                    )),
-                },
-            );
-        }
+    map
+}
 
-        // Up, without ghosts
-        for row in 3..9 {
-            let row_to = row - 3;
-
-            let from = Position::new(row, col).unwrap();
-            let to = Position::new(row_to, col).unwrap();
-
-            map.insert(
-                (from, to),
-                Path {
-                    main_path: linear_path_between(
-                        row as i32,
-                        col as i32,
-                        row_to as i32,
-                        col as i32,
-                    ),
-                    ghost_path: None,
-                },
-            );
-        }
+fn linear_subpath(from: Vec2, to: Vec2) -> SubpathNoId {
+    SubpathNoId::from_bezier(&Bezier::from_linear_coordinates(
+        from.x as f64,
+        from.y as f64,
+        to.x as f64,
+        to.y as f64,
+    ))
+}
 
-        // Up, with ghosts
-        for row in 0..3 {
-            let row_to = row as i32 - 3;
-
-            let from = Position::new(row, col).unwrap();
-            let to = Position::new((row_to + 9) as u8, col).unwrap();
-
-            map.insert(
-                (from, to),
-                Path {
-                    main_path: linear_path_between(
-                        row as i32 + 9,
-                        col as i32,
-                        row_to + 9,
-                        col as i32,
-                    ),
-                    ghost_path: Some(linear_path_between(
-                        row as i32, col as i32, row_to, col as i32,
-                    )),
-                },
-            );
-        }
+/// A slide from one position to another. A slide that wraps around the board
+/// enters from the opposite edge, and its ghost leaves through the near one.
+fn linear_path(from: Position, to: Position) -> Path {
+    let size = from.size();
+    let slide_distance = size.square_size() as i32;
+    let board_dimension = size.dimension() as i32;
+    let location = |row: i32, col: i32| location_vec(size, row as f32, col as f32);
+
+    let (row_from, col_from) = (from.row() as i32, from.col() as i32);
+    let (row_to, col_to) = (to.row() as i32, to.col() as i32);
+    let (row_delta, col_delta) = (row_to - row_from, col_to - col_from);
+
+    let start = location(row_from, col_from);
+    let end = location(row_to, col_to);
+
+    if row_delta.abs() <= slide_distance && col_delta.abs() <= slide_distance {
+        return Path {
+            main_path: linear_subpath(start, end),
+            ghost_path: None,
+        };
     }
 
-    // Rotational
-    #[rustfmt::skip]
-    let rotational_params = [
-        // Northern square, clockwise
-        (/*from*/ 0, 3, /*arc from*/ 0, 5, /*arc through*/ 0, 8, /*arc to*/ 3, 8, /*to*/ 3, 8),
-        (/*from*/ 0, 4, /*arc from*/ 0, 5, /*arc through*/ 0, 8, /*arc to*/ 3, 8, /*to*/ 4, 8),
-        (/*from*/ 0, 5, /*arc from*/ 0, 5, /*arc through*/ 0, 8, /*arc to*/ 3, 8, /*to*/ 5, 8),
-        (/*from*/ 1, 3, /*arc from*/ 1, 5, /*arc through*/ 1, 7, /*arc to*/ 3, 7, /*to*/ 3, 7),
-        (/*from*/ 1, 4, /*arc from*/ 1, 5, /*arc through*/ 1, 7, /*arc to*/ 3, 7, /*to*/ 4, 7),
-        (/*from*/ 1, 5, /*arc from*/ 1, 5, /*arc through*/ 1, 7, /*arc to*/ 3, 7, /*to*/ 5, 7),
-        (/*from*/ 2, 3, /*arc from*/ 2, 5, /*arc through*/ 2, 6, /*arc to*/ 3, 6, /*to*/ 3, 6),
-        (/*from*/ 2, 4, /*arc from*/ 2, 5, /*arc through*/ 2, 6, /*arc to*/ 3, 6, /*to*/ 4, 6),
-        (/*from*/ 2, 5, /*arc from*/ 2, 5, /*arc through*/ 2, 6, /*arc to*/ 3, 6, /*to*/ 5, 6),
-
-        // Northern square, anti-clockwise
-        (/*from*/ 0, 3, /*arc from*/ 0, 3, /*arc through*/ 0, 0, /*arc to*/ 3, 0, /*to*/ 5, 0),
-        (/*from*/ 0, 4, /*arc from*/ 0, 3, /*arc through*/ 0, 0, /*arc to*/ 3, 0, /*to*/ 4, 0),
-        (/*from*/ 0, 5, /*arc from*/ 0, 3, /*arc through*/ 0, 0, /*arc to*/ 3, 0, /*to*/ 3, 0),
-        (/*from*/ 1, 3, /*arc from*/ 1, 3, /*arc through*/ 1, 1, /*arc to*/ 3, 1, /*to*/ 5, 1),
-        (/*from*/ 1, 4, /*arc from*/ 1, 3, /*arc through*/ 1, 1, /*arc to*/ 3, 1, /*to*/ 4, 1),
-        (/*from*/ 1, 5, /*arc from*/ 1, 3, /*arc through*/ 1, 1, /*arc to*/ 3, 1, /*to*/ 3, 1),
-        (/*from*/ 2, 3, /*arc from*/ 2, 3, /*arc through*/ 2, 2, /*arc to*/ 3, 2, /*to*/ 5, 2),
-        (/*from*/ 2, 4, /*arc from*/ 2, 3, /*arc through*/ 2, 2, /*arc to*/ 3, 2, /*to*/ 4, 2),
-        (/*from*/ 2, 5, /*arc from*/ 2, 3, /*arc through*/ 2, 2, /*arc to*/ 3, 2, /*to*/ 3, 2),
-
-        // Southern square, clockwise
-        (/*from*/ 6, 3, /*arc from*/ 6, 3, /*arc through*/ 6, 2, /*arc to*/ 5, 2, /*to*/ 3, 2),
-        (/*from*/ 6, 4, /*arc from*/ 6, 3, /*arc through*/ 6, 2, /*arc to*/ 5, 2, /*to*/ 4, 2),
-        (/*from*/ 6, 5, /*arc from*/ 6, 3, /*arc through*/ 6, 2, /*arc to*/ 5, 2, /*to*/ 5, 2),
-        (/*from*/ 7, 3, /*arc from*/ 7, 3, /*arc through*/ 7, 1, /*arc to*/ 5, 1, /*to*/ 3, 1),
-        (/*from*/ 7, 4, /*arc from*/ 7, 3, /*arc through*/ 7, 1, /*arc to*/ 5, 1, /*to*/ 4, 1),
-        (/*from*/ 7, 5, /*arc from*/ 7, 3, /*arc through*/ 7, 1, /*arc to*/ 5, 1, /*to*/ 5, 1),
-        (/*from*/ 8, 3, /*arc from*/ 8, 3, /*arc through*/ 8, 0, /*arc to*/ 5, 0, /*to*/ 3, 0),
-        (/*from*/ 8, 4, /*arc from*/ 8, 3, /*arc through*/ 8, 0, /*arc to*/ 5, 0, /*to*/ 4, 0),
-        (/*from*/ 8, 5, /*arc from*/ 8, 3, /*arc through*/ 8, 0, /*arc to*/ 5, 0, /*to*/ 5, 0),
-
-        // Southern square, anti-clockwise
-        (/*from*/ 6, 3, /*arc from*/ 6, 5, /*arc through*/ 6, 6, /*arc to*/ 5, 6, /*to*/ 5, 6),
-        (/*from*/ 6, 4, /*arc from*/ 6, 5, /*arc through*/ 6, 6, /*arc to*/ 5, 6, /*to*/ 4, 6),
-        (/*from*/ 6, 5, /*arc from*/ 6, 5, /*arc through*/ 6, 6, /*arc to*/ 5, 6, /*to*/ 3, 6),
-        (/*from*/ 7, 3, /*arc from*/ 7, 5, /*arc through*/ 7, 7, /*arc to*/ 5, 7, /*to*/ 5, 7),
-        (/*from*/ 7, 4, /*arc from*/ 7, 5, /*arc through*/ 7, 7, /*arc to*/ 5, 7, /*to*/ 4, 7),
-        (/*from*/ 7, 5, /*arc from*/ 7, 5, /*arc through*/ 7, 7, /*arc to*/ 5, 7, /*to*/ 3, 7),
-        (/*from*/ 8, 3, /*arc from*/ 8, 5, /*arc through*/ 8, 8, /*arc to*/ 5, 8, /*to*/ 5, 8),
-        (/*from*/ 8, 4, /*arc from*/ 8, 5, /*arc through*/ 8, 8, /*arc to*/ 5, 8, /*to*/ 4, 8),
-        (/*from*/ 8, 5, /*arc from*/ 8, 5, /*arc through*/ 8, 8, /*arc to*/ 5, 8, /*to*/ 3, 8),
-
-        // Western square, clockwise
-        (/*from*/ 3, 0, /*arc from*/ 3, 0, /*arc through*/ 0, 0, /*arc to*/ 0, 3, /*to*/ 0, 5),
-        (/*from*/ 4, 0, /*arc from*/ 3, 0, /*arc through*/ 0, 0, /*arc to*/ 0, 3, /*to*/ 0, 4),
-        (/*from*/ 5, 0, /*arc from*/ 3, 0, /*arc through*/ 0, 0, /*arc to*/ 0, 3, /*to*/ 0, 3),
-        (/*from*/ 3, 1, /*arc from*/ 3, 1, /*arc through*/ 1, 1, /*arc to*/ 1, 3, /*to*/ 1, 5),
-        (/*from*/ 4, 1, /*arc from*/ 3, 1, /*arc through*/ 1, 1, /*arc to*/ 1, 3, /*to*/ 1, 4),
-        (/*from*/ 5, 1, /*arc from*/ 3, 1, /*arc through*/ 1, 1, /*arc to*/ 1, 3, /*to*/ 1, 3),
-        (/*from*/ 3, 2, /*arc from*/ 3, 2, /*arc through*/ 2, 2, /*arc to*/ 2, 3, /*to*/ 2, 5),
-        (/*from*/ 4, 2, /*arc from*/ 3, 2, /*arc through*/ 2, 2, /*arc to*/ 2, 3, /*to*/ 2, 4),
-        (/*from*/ 5, 2, /*arc from*/ 3, 2, /*arc through*/ 2, 2, /*arc to*/ 2, 3, /*to*/ 2, 3),
-
-        // Western square, anti-clockwise
-        (/*from*/ 3, 0, /*arc from*/ 5, 0, /*arc through*/ 8, 0, /*arc to*/ 8, 3, /*to*/ 8, 3),
-        (/*from*/ 4, 0, /*arc from*/ 5, 0, /*arc through*/ 8, 0, /*arc to*/ 8, 3, /*to*/ 8, 4),
-        (/*from*/ 5, 0, /*arc from*/ 5, 0, /*arc through*/ 8, 0, /*arc to*/ 8, 3, /*to*/ 8, 5),
-        (/*from*/ 3, 1, /*arc from*/ 5, 1, /*arc through*/ 7, 1, /*arc to*/ 7, 3, /*to*/ 7, 3),
-        (/*from*/ 4, 1, /*arc from*/ 5, 1, /*arc through*/ 7, 1, /*arc to*/ 7, 3, /*to*/ 7, 4),
-        (/*from*/ 5, 1, /*arc from*/ 5, 1, /*arc through*/ 7, 1, /*arc to*/ 7, 3, /*to*/ 7, 5),
-        (/*from*/ 3, 2, /*arc from*/ 5, 2, /*arc through*/ 6, 2, /*arc to*/ 6, 3, /*to*/ 6, 3),
-        (/*from*/ 4, 2, /*arc from*/ 5, 2, /*arc through*/ 6, 2, /*arc to*/ 6, 3, /*to*/ 6, 4),
-        (/*from*/ 5, 2, /*arc from*/ 5, 2, /*arc through*/ 6, 2, /*arc to*/ 6, 3, /*to*/ 6, 5),
-
-        // Eastern square, clockwise
-        (/*from*/ 3, 6, /*arc from*/ 6, 6, /*arc through*/ 6, 6, /*arc to*/ 6, 5, /*to*/ 6, 5),
-        (/*from*/ 4, 6, /*arc from*/ 6, 6, /*arc through*/ 6, 6, /*arc to*/ 6, 5, /*to*/ 6, 4),
-        (/*from*/ 5, 6, /*arc from*/ 6, 6, /*arc through*/ 6, 6, /*arc to*/ 6, 5, /*to*/ 6, 3),
-        (/*from*/ 3, 7, /*arc from*/ 6, 7, /*arc through*/ 7, 7, /*arc to*/ 7, 5, /*to*/ 7, 5),
-        (/*from*/ 4, 7, /*arc from*/ 6, 7, /*arc through*/ 7, 7, /*arc to*/ 7, 5, /*to*/ 7, 4),
-        (/*from*/ 5, 7, /*arc from*/ 6, 7, /*arc through*/ 7, 7, /*arc to*/ 7, 5, /*to*/ 7, 3),
-        (/*from*/ 3, 8, /*arc from*/ 6, 8, /*arc through*/ 8, 8, /*arc to*/ 8, 5, /*to*/ 8, 5),
-        (/*from*/ 4, 8, /*arc from*/ 6, 8, /*arc through*/ 8, 8, /*arc to*/ 8, 5, /*to*/ 8, 4),
-        (/*from*/ 5, 8, /*arc from*/ 6, 8, /*arc through*/ 8, 8, /*arc to*/ 8, 5, /*to*/ 8, 3),
-
-        // Eastern square, anti-clockwise
-        (/*from*/ 3, 6, /*arc from*/ 3, 6, /*arc through*/ 2, 6, /*arc to*/ 2, 5, /*to*/ 2, 3),
-        (/*from*/ 4, 6, /*arc from*/ 3, 6, /*arc through*/ 2, 6, /*arc to*/ 2, 5, /*to*/ 2, 4),
-        (/*from*/ 5, 6, /*arc from*/ 3, 6, /*arc through*/ 2, 6, /*arc to*/ 2, 5, /*to*/ 2, 5),
-        (/*from*/ 3, 7, /*arc from*/ 3, 7, /*arc through*/ 1, 7, /*arc to*/ 1, 5, /*to*/ 1, 3),
-        (/*from*/ 4, 7, /*arc from*/ 3, 7, /*arc through*/ 1, 7, /*arc to*/ 1, 5, /*to*/ 1, 4),
-        (/*from*/ 5, 7, /*arc from*/ 3, 7, /*arc through*/ 1, 7, /*arc to*/ 1, 5, /*to*/ 1, 5),
-        (/*from*/ 3, 8, /*arc from*/ 3, 8, /*arc through*/ 0, 8, /*arc to*/ 0, 5, /*to*/ 0, 3),
-        (/*from*/ 4, 8, /*arc from*/ 3, 8, /*arc through*/ 0, 8, /*arc to*/ 0, 5, /*to*/ 0, 4),
-        (/*from*/ 5, 8, /*arc from*/ 3, 8, /*arc through*/ 0, 8, /*arc to*/ 0, 5, /*to*/ 0, 5),
-    ];
+    // The piece went over the edge, so it actually moved the other way
+    let wrap = |delta: i32| -delta.signum() * board_dimension;
+    let (row_wrap, col_wrap) = (wrap(row_delta), wrap(col_delta));
+    let entry = location(row_from - row_wrap, col_from - col_wrap);
+    let exit = location(row_to + row_wrap, col_to + col_wrap);
 
-    for (
-        row_from,
-        col_from,
-        row_arc_from,
-        col_arc_from,
-        row_arc_through,
-        col_arc_through,
-        row_arc_to,
-        col_arc_to,
-        row_to,
-        col_to,
-    ) in rotational_params
-    {
-        let from = Position::new(row_from, col_from).unwrap();
-        let to = Position::new(row_to, col_to).unwrap();
-
-        // TODO Make sure that arc start and stop get a half piece offsest, so
-        // that they fit the paths perfectly.
-        let (x_from, y_from) = piece_location(row_from as i32, col_from as i32);
-        let (x_arc_from, y_arc_from) = piece_location(row_arc_from, col_arc_from);
-        let (x_arc_through, y_arc_through) = piece_location(row_arc_through, col_arc_through);
-        let (x_arc_to, y_arc_to) = piece_location(row_arc_to, col_arc_to);
-        let (x_to, y_to) = piece_location(row_to as i32, col_to as i32);
-
-        let path = SubpathNoId::from_beziers(
-            &[
-                Bezier::from_linear_coordinates(
-                    x_from as f64,
-                    y_from as f64,
-                    x_arc_from as f64,
-                    y_arc_from as f64,
-                ),
-                Bezier::from_quadratic_coordinates(
-                    x_arc_from as f64,
-                    y_arc_from as f64,
-                    x_arc_through as f64,
-                    y_arc_through as f64,
-                    x_arc_to as f64,
-                    y_arc_to as f64,
-                ),
-                Bezier::from_linear_coordinates(
-                    x_arc_to as f64,
-                    y_arc_to as f64,
-                    x_to as f64,
-                    y_to as f64,
-                ),
-            ],
-            false,
-        );
-
-        map.insert(
-            (from, to),
-            Path {
-                main_path: path,
-                ghost_path: None,
-            },
-        );
+    Path {
+        main_path: linear_subpath(entry, end),
+        ghost_path: Some(linear_subpath(start, exit)),
     }
-
-    map
 }
 
-pub fn initialize_pieces<'a>(assets: &'a Assets, paths: &'a PathMap) -> PieceCollection<'a> {
-    let mut pieces = vec![];
-
-    let piece_groups = vec![
-        (&assets.img_piece_green, 0, 3),
-        (&assets.img_piece_purple, 3, 0),
-        (&assets.img_piece_yellow, 3, 3),
-        (&assets.img_piece_blue, 3, 6),
-        (&assets.img_piece_red, 6, 3),
-    ];
-
-    for (texture, row_offset, col_offset) in piece_groups {
-        for row in row_offset..row_offset + 3 {
-            for col in col_offset..col_offset + 3 {
-                let (x, y) = piece_location(row as i32, col as i32);
-                pieces.push(Piece::new(texture, Position::new(row, col).unwrap(), x, y));
-            }
-        }
+/// A rotation from one position to another, along a circle around the
+/// center of the board.
+fn rotational_path(from: Position, to: Position) -> Path {
+    // Rotations turn the board around its middle, so the top left corners of
+    // all the pieces turn around the top left corner a piece there would have
+    let size = from.size();
+    let middle = (size.dimension() - 1) as f32 / 2.;
+    let center = location_vec(size, middle, middle);
+    let start = location_vec(size, from.row() as f32, from.col() as f32) - center;
+    let end = location_vec(size, to.row() as f32, to.col() as f32) - center;
+
+    Path {
+        main_path: arc(center, start, start.angle_between(end)),
+        ghost_path: None,
     }
+}
 
-    PieceCollection::new(paths, pieces)
+/// A cubic bezier approximation of the arc that turns `start`, relative to
+/// `center`, by `angle`. Good enough for arcs of up to a quarter of a circle.
+fn arc(center: Vec2, start: Vec2, angle: f32) -> SubpathNoId {
+    let end = Vec2::from_angle(angle).rotate(start);
+    let handle_length = 4. / 3. * (angle / 4.).tan();
+
+    let points = [
+        start,
+        start + start.perp() * handle_length,
+        end - end.perp() * handle_length,
+        end,
+    ]
+    .map(|point| center + point);
+
+    SubpathNoId::from_bezier(&Bezier::from_cubic_coordinates(
+        points[0].x as f64,
+        points[0].y as f64,
+        points[1].x as f64,
+        points[1].y as f64,
+        points[2].x as f64,
+        points[2].y as f64,
+        points[3].x as f64,
+        points[3].y as f64,
+    ))
 }
 
-pub fn initialize_solved_markers(assets: &Assets) -> Vec<SolvedMarker> {
+pub fn initialize_pieces<'a>(
+    assets: &'a Assets,
+    paths: &'a PathMap,
+    size: BoardSize,
+) -> PieceCollection<'a> {
+    let state = BoardState::new(size);
+
+    let pieces = state
+        .iter()
+        .map(|(position, color)| {
+            let texture = match color {
+                Color::Yellow => &assets.img_piece_yellow,
+                Color::Blue => &assets.img_piece_blue,
+                Color::Red => &assets.img_piece_red,
+                Color::Purple => &assets.img_piece_purple,
+                Color::Green => &assets.img_piece_green,
+            };
+
+            let (x, y) = piece_location(size, position.row() as i32, position.col() as i32);
+            Piece::new(texture, color, position, x, y, piece_size(size))
+        })
+        .collect();
+
+    PieceCollection::new(paths, state, pieces)
+}
+
+/// Markers for the solved squares. Their images only fit the standard board,
+/// the other sizes always draw them with shapes.
+pub fn initialize_solved_markers(assets: &Assets, size: BoardSize) -> Vec<SolvedMarker<'_>> {
     use position::Square::*;
 
     #[rustfmt::skip]
     let markers = vec![
-        SolvedMarker::new(178.374, 178.374, Center, &assets.img_square_solved_center, 0.0),
-        SolvedMarker::new(178.374, 42.660, North, &assets.img_square_solved_edges, 0.0),
-        SolvedMarker::new(178.374, 314.087, South, &assets.img_square_solved_edges, PI),
-        SolvedMarker::new(42.660, 178.374, West, &assets.img_square_solved_edges, -FRAC_PI_2),
-        SolvedMarker::new(314.087, 178.374, East, &assets.img_square_solved_edges, FRAC_PI_2),
+        SolvedMarker::new(178.374, 178.374, Center, &assets.img_square_solved_center, 0.0, size),
+        SolvedMarker::new(178.374, 42.660, North, &assets.img_square_solved_edges, 0.0, size),
+        SolvedMarker::new(178.374, 314.087, South, &assets.img_square_solved_edges, PI, size),
+        SolvedMarker::new(42.660, 178.374, West, &assets.img_square_solved_edges, -FRAC_PI_2, size),
+        SolvedMarker::new(314.087, 178.374, East, &assets.img_square_solved_edges, FRAC_PI_2, size),
     ];
 
     markers
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Manipulation;
+    use bezier_rs::SubpathTValue;
+    use test_case::test_case;
+
+    const EPSILON: f32 = 1e-3;
+
+    fn point_at(path: &SubpathNoId, t: f64) -> Vec2 {
+        let point = path.evaluate(SubpathTValue::GlobalEuclidean(t));
+        Vec2::new(point.x as f32, point.y as f32)
+    }
+
+    fn location_of(position: Position) -> Vec2 {
+        location_vec(
+            position.size(),
+            position.row() as f32,
+            position.col() as f32,
+        )
+    }
+
+    #[test_case(BoardSize::new(2).unwrap())]
+    #[test_case(BoardSize::STANDARD)]
+    #[test_case(BoardSize::new(5).unwrap())]
+    fn every_manipulation_has_a_path_for_every_piece(size: BoardSize) {
+        let paths = initialize_paths(size);
+
+        for manipulation in Manipulation::all(size) {
+            for from in Position::all(size).filter(|position| manipulation.affects(position)) {
+                let mut to = from;
+                to.apply_manipulation(manipulation.piece_manipulation())
+                    .unwrap();
+
+                assert!(
+                    paths.contains_key(&(from, to)),
+                    "Missing path for {:?} -> {:?}",
+                    from,
+                    to
+                );
+            }
+        }
+    }
+
+    #[test_case(BoardSize::new(2).unwrap())]
+    #[test_case(BoardSize::STANDARD)]
+    #[test_case(BoardSize::new(4).unwrap())]
+    fn paths_end_at_the_destination(size: BoardSize) {
+        for ((from, to), path) in initialize_paths(size) {
+            let end = point_at(&path.main_path, 1.);
+            assert!(end.distance(location_of(to)) < EPSILON);
+
+            let start = match &path.ghost_path {
+                Some(ghost) => point_at(ghost, 0.),
+                None => point_at(&path.main_path, 0.),
+            };
+            assert!(start.distance(location_of(from)) < EPSILON);
+        }
+    }
+
+    #[test]
+    fn rotations_stay_on_a_circle_around_the_center() {
+        let size = BoardSize::STANDARD;
+        let center = location_vec(size, 4., 4.);
+        let paths = initialize_paths(size);
+        let from = Position::new(size, 0, 3).unwrap();
+        let mut to = from;
+        to.apply_manipulation(position::Manipulation::RotateClockwise)
+            .unwrap();
+
+        let path = &paths[&(from, to)].main_path;
+        let radius = location_of(from).distance(center);
+
+        for t in [0.1, 0.25, 0.5, 0.75, 0.9] {
+            let distance = point_at(path, t).distance(center);
+            assert!((distance - radius).abs() / radius < EPSILON);
+        }
+    }
+}
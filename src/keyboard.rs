@@ -0,0 +1,633 @@
+//! Keyboard controls.
+//!
+//! The board can be played with a cursor that selects a row, a column or a
+//! ring. The arrow keys slide the selected row or column along its direction
+//! and move the cursor across it, `Q` and `E` rotate the selected ring and
+//! `Tab` switches between selecting rows, columns and rings. Every
+//! manipulation of the standard board also has its own hotkey, which moves
+//! the line in the same position on other board sizes, see
+//! [hotkey_manipulation].
+//!
+//! The key map is stored as lines of `<key> = <action>`, where the key is
+//! the name of a [KeyCode] variant and the action is either a manipulation
+//! in [crate::notation] or one of `left`, `right`, `up`, `down`, `clockwise`,
+//! `anticlockwise`, `next-selection`, `hint`, `colorblind-mode` and
+//! `renderer`. Lines starting with `#` are ignored.
+
+use std::{collections::HashMap, io};
+
+use macroquad::input::KeyCode;
+
+use crate::{storage::Storage, BoardSize, Manipulation, Position};
+
+const STORAGE_KEY: &str = "keymap";
+
+/// Keys that can be used in a key map.
+const KEYS: &[KeyCode] = {
+    use KeyCode::*;
+
+    &[
+        Space,
+        Apostrophe,
+        Comma,
+        Minus,
+        Period,
+        Slash,
+        Key0,
+        Key1,
+        Key2,
+        Key3,
+        Key4,
+        Key5,
+        Key6,
+        Key7,
+        Key8,
+        Key9,
+        Semicolon,
+        Equal,
+        A,
+        B,
+        C,
+        D,
+        E,
+        F,
+        G,
+        H,
+        I,
+        J,
+        K,
+        L,
+        M,
+        N,
+        O,
+        P,
+        Q,
+        R,
+        S,
+        T,
+        U,
+        V,
+        W,
+        X,
+        Y,
+        Z,
+        LeftBracket,
+        Backslash,
+        RightBracket,
+        GraveAccent,
+        Enter,
+        Tab,
+        Backspace,
+        Insert,
+        Delete,
+        Right,
+        Left,
+        Down,
+        Up,
+        PageUp,
+        PageDown,
+        Home,
+        End,
+        F1,
+        F2,
+        F3,
+        F4,
+        F5,
+        F6,
+        F7,
+        F8,
+        F9,
+        F10,
+        F11,
+        F12,
+        Kp0,
+        Kp1,
+        Kp2,
+        Kp3,
+        Kp4,
+        Kp5,
+        Kp6,
+        Kp7,
+        Kp8,
+        Kp9,
+    ]
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    Left,
+    Right,
+    Up,
+    Down,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyAction {
+    Manipulation(Manipulation),
+    /// Slide the selection in the direction or move the cursor to the next one.
+    Cursor(Direction),
+    RotateClockwise,
+    RotateAnticlockwise,
+    NextSelection,
+    Hint,
+    ToggleColorblindMode,
+    ToggleRenderer,
+}
+
+impl KeyAction {
+    /// Every action, controls first. Only the manipulations of the standard
+    /// board are included.
+    pub fn all() -> Vec<KeyAction> {
+        use Direction::*;
+
+        let controls = [
+            KeyAction::Cursor(Left),
+            KeyAction::Cursor(Right),
+            KeyAction::Cursor(Up),
+            KeyAction::Cursor(Down),
+            KeyAction::RotateClockwise,
+            KeyAction::RotateAnticlockwise,
+            KeyAction::NextSelection,
+            KeyAction::Hint,
+            KeyAction::ToggleColorblindMode,
+            KeyAction::ToggleRenderer,
+        ];
+
+        controls
+            .into_iter()
+            .chain(
+                Manipulation::all(BoardSize::STANDARD)
+                    .into_iter()
+                    .map(KeyAction::Manipulation),
+            )
+            .collect()
+    }
+
+    pub fn name(&self) -> String {
+        use Direction::*;
+
+        match self {
+            KeyAction::Manipulation(manipulation) => manipulation.to_string(),
+            KeyAction::Cursor(Left) => "left".into(),
+            KeyAction::Cursor(Right) => "right".into(),
+            KeyAction::Cursor(Up) => "up".into(),
+            KeyAction::Cursor(Down) => "down".into(),
+            KeyAction::RotateClockwise => "clockwise".into(),
+            KeyAction::RotateAnticlockwise => "anticlockwise".into(),
+            KeyAction::NextSelection => "next-selection".into(),
+            KeyAction::Hint => "hint".into(),
+            KeyAction::ToggleColorblindMode => "colorblind-mode".into(),
+            KeyAction::ToggleRenderer => "renderer".into(),
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Self> {
+        use Direction::*;
+
+        let action = match name {
+            "left" => KeyAction::Cursor(Left),
+            "right" => KeyAction::Cursor(Right),
+            "up" => KeyAction::Cursor(Up),
+            "down" => KeyAction::Cursor(Down),
+            "clockwise" => KeyAction::RotateClockwise,
+            "anticlockwise" => KeyAction::RotateAnticlockwise,
+            "next-selection" => KeyAction::NextSelection,
+            "hint" => KeyAction::Hint,
+            "colorblind-mode" => KeyAction::ToggleColorblindMode,
+            "renderer" => KeyAction::ToggleRenderer,
+            _ => KeyAction::Manipulation(name.parse().ok()?),
+        };
+
+        Some(action)
+    }
+}
+
+pub fn key_name(key: KeyCode) -> String {
+    format!("{:?}", key)
+}
+
+fn key_from_name(name: &str) -> Option<KeyCode> {
+    KEYS.iter().copied().find(|key| key_name(*key) == name)
+}
+
+/// The row, column or ring the keyboard cursor is on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Selection {
+    Row(u8),
+    Column(u8),
+    Ring(u8),
+}
+
+impl Selection {
+    /// The selection a game on a board of the size starts with, the middle
+    /// row or the lower of the two middle rows.
+    pub fn new(size: BoardSize) -> Self {
+        let lines = size.middle_lines();
+        Selection::Row(lines.start + size.square_size() / 2)
+    }
+
+    pub fn contains(&self, position: &Position) -> bool {
+        match *self {
+            Selection::Row(row) => position.row() == row,
+            Selection::Column(col) => position.col() == col,
+            Selection::Ring(ring) => position.ring() == Some(ring),
+        }
+    }
+
+    /// The manipulation that a cursor key makes, if the selection can move in
+    /// that direction. Rings are rotated with left and right.
+    pub fn manipulation(&self, direction: Direction) -> Option<Manipulation> {
+        use Direction::*;
+
+        match (*self, direction) {
+            (Selection::Row(row), Left) => Some(Manipulation::SlideLeft(row)),
+            (Selection::Row(row), Right) => Some(Manipulation::SlideRight(row)),
+            (Selection::Column(col), Up) => Some(Manipulation::SlideUp(col)),
+            (Selection::Column(col), Down) => Some(Manipulation::SlideDown(col)),
+            (Selection::Ring(ring), Left) => Some(Manipulation::RotateAnticlockwise(ring)),
+            (Selection::Ring(ring), Right) => Some(Manipulation::RotateClockwise(ring)),
+            _ => None,
+        }
+    }
+
+    /// The selection next to this one in the direction on a board of the
+    /// size, staying on the same kind of selection. Up and down move between
+    /// rings, with up going outward.
+    pub fn moved(&self, direction: Direction, size: BoardSize) -> Selection {
+        use Direction::*;
+
+        let lines = size.middle_lines();
+        let last_ring = size.rings().end - 1;
+        let previous_line = |line: u8| line.saturating_sub(1).max(lines.start);
+        let next_line = |line: u8| (line + 1).min(lines.end - 1);
+
+        match (*self, direction) {
+            (Selection::Row(row), Up) => Selection::Row(previous_line(row)),
+            (Selection::Row(row), Down) => Selection::Row(next_line(row)),
+            (Selection::Column(col), Left) => Selection::Column(previous_line(col)),
+            (Selection::Column(col), Right) => Selection::Column(next_line(col)),
+            (Selection::Ring(ring), Up) => Selection::Ring((ring + 1).min(last_ring)),
+            (Selection::Ring(ring), Down) => Selection::Ring(ring.saturating_sub(1)),
+            (selection, _) => selection,
+        }
+    }
+
+    /// The rotation of the selected ring, if a ring is selected.
+    pub fn rotation(&self, clockwise: bool) -> Option<Manipulation> {
+        match *self {
+            Selection::Ring(ring) if clockwise => Some(Manipulation::RotateClockwise(ring)),
+            Selection::Ring(ring) => Some(Manipulation::RotateAnticlockwise(ring)),
+            _ => None,
+        }
+    }
+
+    /// Switch from rows to columns to rings and back, keeping the same index
+    /// on a board of the size.
+    pub fn next(&self, size: BoardSize) -> Selection {
+        let first_line = size.middle_lines().start;
+
+        match *self {
+            Selection::Row(row) => Selection::Column(row),
+            Selection::Column(col) => Selection::Ring(col - first_line),
+            Selection::Ring(ring) => Selection::Row(ring + first_line),
+        }
+    }
+}
+
+/// Mapping from keys to the actions they trigger.
+#[derive(Debug, Clone, PartialEq)]
+pub struct KeyMap {
+    bindings: HashMap<KeyCode, KeyAction>,
+}
+
+impl Default for KeyMap {
+    fn default() -> Self {
+        use KeyCode::*;
+        use Manipulation::*;
+
+        let manipulations = [
+            (Key1, SlideLeft(3)),
+            (Key2, SlideLeft(4)),
+            (Key3, SlideLeft(5)),
+            (Key4, SlideRight(3)),
+            (Key5, SlideRight(4)),
+            (Key6, SlideRight(5)),
+            (U, SlideUp(3)),
+            (I, SlideUp(4)),
+            (O, SlideUp(5)),
+            (J, SlideDown(3)),
+            (K, SlideDown(4)),
+            (L, SlideDown(5)),
+            (Key7, RotateClockwise(0)),
+            (Key8, RotateClockwise(1)),
+            (Key9, RotateClockwise(2)),
+            (M, RotateAnticlockwise(0)),
+            (Comma, RotateAnticlockwise(1)),
+            (Period, RotateAnticlockwise(2)),
+        ]
+        .map(|(key, manipulation)| (key, KeyAction::Manipulation(manipulation)));
+
+        let controls = [
+            (Left, KeyAction::Cursor(Direction::Left)),
+            (Right, KeyAction::Cursor(Direction::Right)),
+            (Up, KeyAction::Cursor(Direction::Up)),
+            (Down, KeyAction::Cursor(Direction::Down)),
+            (E, KeyAction::RotateClockwise),
+            (Q, KeyAction::RotateAnticlockwise),
+            (Tab, KeyAction::NextSelection),
+            (H, KeyAction::Hint),
+            (B, KeyAction::ToggleColorblindMode),
+            (V, KeyAction::ToggleRenderer),
+        ];
+
+        Self {
+            bindings: manipulations.into_iter().chain(controls).collect(),
+        }
+    }
+}
+
+impl KeyMap {
+    /// Load the key map from the storage, skipping any malformed bindings.
+    /// The default key map is used if none is stored.
+    pub fn load(storage: &dyn Storage) -> Self {
+        storage
+            .load(STORAGE_KEY)
+            .map(|text| Self::parse(&text))
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self, storage: &mut dyn Storage) -> io::Result<()> {
+        storage.save(STORAGE_KEY, &self.to_text())
+    }
+
+    fn parse(text: &str) -> Self {
+        let bindings = text
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .filter_map(|line| {
+                let (key, action) = line.split_once('=')?;
+                Some((
+                    key_from_name(key.trim())?,
+                    KeyAction::from_name(action.trim())?,
+                ))
+            })
+            .collect();
+
+        Self { bindings }
+    }
+
+    fn to_text(&self) -> String {
+        let mut lines: Vec<_> = self
+            .bindings
+            .iter()
+            .map(|(key, action)| format!("{} = {}", key_name(*key), action.name()))
+            .collect();
+        lines.sort();
+
+        lines.join("\n")
+    }
+
+    pub fn action(&self, key: KeyCode) -> Option<KeyAction> {
+        self.bindings.get(&key).copied()
+    }
+
+    pub fn bind(&mut self, key: KeyCode, action: KeyAction) {
+        self.bindings.insert(key, action);
+    }
+
+    pub fn unbind(&mut self, key: KeyCode) {
+        self.bindings.remove(&key);
+    }
+
+    /// The keys bound to the action, sorted by name.
+    pub fn keys(&self, action: KeyAction) -> Vec<KeyCode> {
+        let mut keys: Vec<_> = self
+            .bindings
+            .iter()
+            .filter(|(_, bound)| **bound == action)
+            .map(|(key, _)| *key)
+            .collect();
+        keys.sort_by_key(|key| key_name(*key));
+
+        keys
+    }
+
+    /// Make the key the only one bound to the action. Keys that can't be
+    /// stored in a key map are refused and leave it unchanged.
+    pub fn rebind(&mut self, action: KeyAction, key: KeyCode) -> bool {
+        if !KEYS.contains(&key) {
+            return false;
+        }
+
+        self.bindings.retain(|_, bound| *bound != action);
+        self.bind(key, action);
+        true
+    }
+}
+
+/// The manipulation a hotkey bound to the manipulation makes on a board of
+/// the size. Hotkeys are bound to manipulations of the standard board, which
+/// move the row, column or ring in the same position on other sizes, counted
+/// from the first middle row or column and from the innermost ring. With
+/// `shift` they move the line three positions further, to reach the lines the
+/// standard board doesn't have. Bound manipulations that aren't on the
+/// standard board are made as they are, if the board has them.
+pub fn hotkey_manipulation(
+    manipulation: Manipulation,
+    size: BoardSize,
+    shift: bool,
+) -> Option<Manipulation> {
+    use Manipulation::*;
+
+    let standard = BoardSize::STANDARD;
+    if !Manipulation::all(standard).contains(&manipulation) {
+        return Some(manipulation).filter(|m| Manipulation::all(size).contains(m));
+    }
+
+    let offset = if shift { standard.square_size() } else { 0 };
+    let line = |index: u8| {
+        let position = index - standard.middle_lines().start + offset;
+        size.middle_lines().nth(position as usize)
+    };
+    let ring = |index: u8| size.rings().nth((index + offset) as usize);
+
+    match manipulation {
+        SlideLeft(row) => line(row).map(SlideLeft),
+        SlideRight(row) => line(row).map(SlideRight),
+        SlideUp(col) => line(col).map(SlideUp),
+        SlideDown(col) => line(col).map(SlideDown),
+        RotateClockwise(ring_index) => ring(ring_index).map(RotateClockwise),
+        RotateAnticlockwise(ring_index) => ring(ring_index).map(RotateAnticlockwise),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_case::test_case;
+    use Direction::*;
+    use Manipulation::*;
+
+    #[test_case(2, SlideLeft(3), false, Some(SlideLeft(2)); "first row of the smallest board")]
+    #[test_case(2, SlideLeft(5), false, None; "smallest board has two rows")]
+    #[test_case(3, SlideUp(4), false, Some(SlideUp(4)); "standard board")]
+    #[test_case(3, SlideUp(4), true, None; "standard board has nothing further")]
+    #[test_case(5, SlideDown(3), false, Some(SlideDown(5)); "first column of the largest board")]
+    #[test_case(5, SlideRight(4), true, Some(SlideRight(9)); "last row of the largest board")]
+    #[test_case(5, RotateClockwise(1), true, Some(RotateClockwise(4)); "outermost ring of the largest board")]
+    #[test_case(4, RotateAnticlockwise(0), true, Some(RotateAnticlockwise(3)); "outermost ring of a larger board")]
+    #[test_case(5, SlideUp(9), false, Some(SlideUp(9)); "move of another size as it is")]
+    #[test_case(3, SlideUp(9), false, None; "move of another size on the standard board")]
+    fn hotkey_moves_the_line_in_the_same_position(
+        square_size: u8,
+        manipulation: Manipulation,
+        shift: bool,
+        expected: Option<Manipulation>,
+    ) {
+        let size = BoardSize::new(square_size).unwrap();
+
+        assert_eq!(hotkey_manipulation(manipulation, size, shift), expected);
+    }
+
+    #[test_case(2)]
+    #[test_case(4)]
+    #[test_case(5)]
+    fn default_hotkeys_reach_every_manipulation(square_size: u8) {
+        let size = BoardSize::new(square_size).unwrap();
+        let key_map = KeyMap::default();
+
+        let reached: Vec<_> = key_map
+            .bindings
+            .values()
+            .filter_map(|action| match action {
+                KeyAction::Manipulation(manipulation) => Some(*manipulation),
+                _ => None,
+            })
+            .flat_map(|manipulation| {
+                [false, true]
+                    .into_iter()
+                    .filter_map(move |shift| hotkey_manipulation(manipulation, size, shift))
+            })
+            .collect();
+
+        for manipulation in Manipulation::all(size) {
+            assert!(
+                reached.contains(&manipulation),
+                "{} has no hotkey",
+                manipulation
+            );
+        }
+    }
+
+    #[test]
+    fn default_key_map_binds_every_manipulation() {
+        let key_map = KeyMap::default();
+
+        for manipulation in Manipulation::all(BoardSize::STANDARD) {
+            let action = KeyAction::Manipulation(manipulation);
+            assert!(key_map.bindings.values().any(|bound| *bound == action));
+        }
+    }
+
+    #[test]
+    fn key_map_round_trips() {
+        let key_map = KeyMap::default();
+
+        assert_eq!(KeyMap::parse(&key_map.to_text()), key_map);
+    }
+
+    #[test]
+    fn every_action_has_a_name() {
+        for action in KeyAction::all() {
+            assert_eq!(KeyAction::from_name(&action.name()), Some(action));
+        }
+    }
+
+    #[test]
+    fn rebinding_replaces_the_keys_of_the_action() {
+        let mut key_map = KeyMap::default();
+        let hint = KeyAction::Hint;
+
+        assert!(key_map.rebind(hint, KeyCode::Key1));
+        assert!(!key_map.rebind(hint, KeyCode::Escape));
+
+        assert_eq!(key_map.keys(hint), vec![KeyCode::Key1]);
+        assert_eq!(key_map.action(KeyCode::H), None);
+    }
+
+    #[test]
+    fn key_map_skips_malformed_bindings() {
+        let key_map = KeyMap::parse("# comment\nW = U4\nF13 = L3\nX = jump\nY\n A=C1' ");
+
+        assert_eq!(key_map.bindings.len(), 2);
+        assert_eq!(
+            key_map.action(KeyCode::W),
+            Some(KeyAction::Manipulation(Manipulation::SlideUp(4)))
+        );
+        assert_eq!(
+            key_map.action(KeyCode::A),
+            Some(KeyAction::Manipulation(Manipulation::RotateAnticlockwise(
+                1
+            )))
+        );
+    }
+
+    #[test_case(Selection::Row(3), Left, Some(Manipulation::SlideLeft(3)))]
+    #[test_case(Selection::Row(5), Up, None)]
+    #[test_case(Selection::Column(4), Down, Some(Manipulation::SlideDown(4)))]
+    #[test_case(Selection::Column(4), Right, None)]
+    #[test_case(Selection::Ring(2), Right, Some(Manipulation::RotateClockwise(2)))]
+    #[test_case(Selection::Ring(0), Up, None)]
+    fn cursor_key_makes_expected_manipulation(
+        selection: Selection,
+        direction: Direction,
+        expected: Option<Manipulation>,
+    ) {
+        assert_eq!(selection.manipulation(direction), expected);
+    }
+
+    #[test_case(Selection::Row(4), Up, Selection::Row(3))]
+    #[test_case(Selection::Row(3), Up, Selection::Row(3); "row stays on the board")]
+    #[test_case(Selection::Column(4), Right, Selection::Column(5))]
+    #[test_case(Selection::Column(5), Right, Selection::Column(5); "column stays on the board")]
+    #[test_case(Selection::Ring(0), Up, Selection::Ring(1))]
+    #[test_case(Selection::Ring(0), Down, Selection::Ring(0); "innermost ring")]
+    fn cursor_moves_to_expected_selection(
+        selection: Selection,
+        direction: Direction,
+        expected: Selection,
+    ) {
+        assert_eq!(selection.moved(direction, BoardSize::STANDARD), expected);
+    }
+
+    #[test]
+    fn next_selection_cycles_through_kinds() {
+        let size = BoardSize::STANDARD;
+        let start = Selection::Row(5);
+
+        assert_eq!(start.next(size), Selection::Column(5));
+        assert_eq!(start.next(size).next(size), Selection::Ring(2));
+        assert_eq!(start.next(size).next(size).next(size), start);
+    }
+
+    #[test_case(2, Selection::Row(3))]
+    #[test_case(3, Selection::Row(4))]
+    #[test_case(4, Selection::Row(6))]
+    #[test_case(5, Selection::Row(7))]
+    fn selection_starts_in_the_middle(square_size: u8, expected: Selection) {
+        assert_eq!(
+            Selection::new(BoardSize::new(square_size).unwrap()),
+            expected
+        );
+    }
+
+    #[test]
+    fn cursor_stays_on_larger_boards() {
+        let size = BoardSize::new(5).unwrap();
+
+        assert_eq!(Selection::Row(9).moved(Down, size), Selection::Row(9));
+        assert_eq!(Selection::Ring(3).moved(Up, size), Selection::Ring(4));
+        assert_eq!(Selection::Ring(4).moved(Up, size), Selection::Ring(4));
+    }
+}
@@ -2,23 +2,48 @@ mod button;
 pub use button::Button;
 
 mod pieces;
-pub use pieces::collection::Manipulation;
+pub use pieces::board::BoardState;
+pub use pieces::board::Color;
+pub use pieces::board::Manipulation;
+pub use pieces::board::StateError;
 pub use pieces::collection::PieceCollection;
+pub use pieces::easing::Easing;
 pub use pieces::path::Path;
 pub use pieces::path::SubpathNoId;
 pub use pieces::piece::Piece;
+pub use pieces::position::BoardSize;
 pub use pieces::position::PieceError;
 pub use pieces::position::Position;
 
 pub mod game;
+pub mod gesture;
+pub mod history;
 pub mod initialization;
+pub mod keyboard;
+pub mod menu;
+pub mod notation;
+pub mod options;
+pub mod pointer;
+pub mod ruleset;
+pub mod scramble;
+pub mod settings;
 pub mod solved_marker;
+pub mod solver;
+pub mod statistics;
+pub mod storage;
+pub mod theme;
+pub mod vector;
+pub mod viewport;
 
-use game::Game;
+use game::{Game, GameEnd};
 use initialization::{
     initialize_buttons, initialize_paths, initialize_pieces, initialize_solved_markers, load_assets,
 };
 use macroquad::prelude::*;
+use options::Options;
+use settings::Settings;
+use statistics::Statistics;
+use theme::{Theme, ThemeSelection};
 
 fn window_conf() -> Conf {
     Conf {
@@ -32,24 +57,62 @@ fn window_conf() -> Conf {
 
 #[macroquad::main(window_conf)]
 async fn main() {
-    let assets = load_assets().await;
-    let paths = initialize_paths();
-
-    let mut game = Game {
-        assets: &assets,
-        buttons: initialize_buttons(&assets),
-        solved_markers: initialize_solved_markers(&assets),
-        pieces: initialize_pieces(&assets, &paths),
-    };
-
-    rand::srand(macroquad::miniquad::date::now() as u64);
-
-    game.wait(1.0).await;
-    game.run_shuffle(20, 0.15).await;
-    game.wait(0.5).await;
-    game.run_blink_loop(0.3).await;
-    game.wait(0.5).await;
-    game.run_main_loop().await;
-    game.wait(1.0).await;
-    game.run_victory_loop().await;
+    // Touches are handled separately, so they shouldn't also move the mouse
+    simulate_mouse_with_touch(false);
+
+    let options = Options::parse(&quad_url::get_program_parameters());
+
+    let mut storage = storage::default_storage();
+    let mut settings = Settings::load(storage.as_ref());
+    let mut statistics = Statistics::load(storage.as_ref());
+
+    let mut theme_selection = ThemeSelection::new(options.theme.clone(), &settings);
+
+    let mut seed = options.seed.unwrap_or_else(scramble::random_seed);
+
+    loop {
+        let theme = match theme_selection.directory(&settings) {
+            Some(directory) => Theme::load(&directory).await.unwrap_or_else(|error| {
+                println!("Using the default theme: {}", error);
+                Theme::default()
+            }),
+            None => Theme::default(),
+        };
+
+        let size = settings.ruleset.board_size().unwrap_or(settings.board_size);
+
+        let assets = load_assets(&theme).await;
+        let paths = initialize_paths(size);
+
+        let mut game = Game::new(
+            &assets,
+            initialize_buttons(&assets, size),
+            initialize_solved_markers(&assets, size),
+            initialize_pieces(&assets, &paths, size),
+            seed,
+            settings,
+            storage,
+        );
+
+        game.wait(1.0).await;
+        game.run_shuffle(0.15, 4.0).await;
+        game.wait(0.5).await;
+        game.run_blink_loop(0.3).await;
+        game.wait(0.5).await;
+
+        if game.run_main_loop().await == GameEnd::Solved {
+            let solve = game.solve();
+            statistics.record(solve.clone());
+            if let Err(error) = statistics.save(game.storage_mut()) {
+                println!("Failed to save statistics: {}", error);
+            }
+
+            game.wait(1.0).await;
+            game.run_victory_loop(&statistics.comparable_to(&solve))
+                .await;
+        }
+
+        (settings, storage) = game.into_settings();
+        seed = scramble::random_seed();
+    }
 }
@@ -0,0 +1,616 @@
+//! The settings screen.
+//!
+//! The screen is opened with the gear button or `Escape` and is drawn over
+//! the board. Every setting is changed by clicking it, which goes to its next
+//! value, and is applied right away, except for the scramble, the board size,
+//! the rules and the theme, which are used by the next game. A new game can be
+//! started from the menu or by clicking after a win. Key bindings have their
+//! own page, where clicking an action waits for the key to bind to it.
+
+use macroquad::{
+    color::Color,
+    color_u8,
+    input::KeyCode,
+    math::{Rect, Vec2},
+    shapes::{draw_rectangle, draw_rectangle_lines},
+    text::{draw_text, measure_text},
+};
+
+use crate::{
+    button::MouseEvent,
+    keyboard::{key_name, KeyAction, KeyMap},
+    ruleset::Ruleset,
+    settings::{AnimationSpeed, Renderer, Scramble, Settings, Volume},
+    theme,
+    viewport::BOARD_SIZE,
+    BoardState, Easing,
+};
+
+/// Key that opens the menu and closes it or goes back a page.
+pub const MENU_KEY: KeyCode = KeyCode::Escape;
+
+const OVERLAY_COLOR: Color = color_u8!(0x21, 0x21, 0x21, 0xa0);
+const PANEL_COLOR: Color = color_u8!(0x67, 0x67, 0x67, 0xff);
+const BORDER_COLOR: Color = color_u8!(0x21, 0x21, 0x21, 0xff);
+const HOVER_COLOR: Color = color_u8!(0x21, 0x21, 0x21, 0x60);
+const TEXT_COLOR: Color = color_u8!(0xff, 0xe6, 0x6d, 0xff);
+const VALUE_COLOR: Color = color_u8!(0xff, 0xff, 0xff, 0xff);
+
+const TITLE_FONT_SIZE: f32 = 28.;
+const PADDING: f32 = 10.;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Page {
+    Settings,
+    Bindings,
+}
+
+impl Page {
+    /// Area of the panel the page is drawn on.
+    fn panel(&self) -> Rect {
+        match self {
+            Page::Settings => Rect::new(90., 20., 320., 460.),
+            Page::Bindings => Rect::new(15., 15., 470., 470.),
+        }
+    }
+
+    fn title(&self) -> &'static str {
+        match self {
+            Page::Settings => "Settings",
+            Page::Bindings => "Key bindings",
+        }
+    }
+
+    fn font_size(&self) -> f32 {
+        match self {
+            Page::Settings => 22.,
+            Page::Bindings => 17.,
+        }
+    }
+}
+
+/// Something on a page that can be clicked.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Item {
+    AnimationSpeed,
+    Easing,
+    QueueMoves,
+    FastMode,
+    Scramble,
+    BoardSize,
+    Ruleset,
+    Renderer,
+    Theme,
+    ColorblindMode,
+    Volume,
+    Bindings,
+    NewGame,
+    Close,
+    Binding(KeyAction),
+    ResetBindings,
+    Back,
+}
+
+impl Item {
+    fn label(&self) -> String {
+        match self {
+            Item::AnimationSpeed => "Animation speed".into(),
+            Item::Easing => "Easing".into(),
+            Item::QueueMoves => "Queue moves".into(),
+            Item::FastMode => "Fast mode".into(),
+            Item::Scramble => "Scramble".into(),
+            Item::BoardSize => "Board size".into(),
+            Item::Ruleset => "Rules".into(),
+            Item::Renderer => "Renderer".into(),
+            Item::Theme => "Theme".into(),
+            Item::ColorblindMode => "Colorblind mode".into(),
+            Item::Volume => "Volume".into(),
+            Item::Bindings => "Key bindings".into(),
+            Item::NewGame => "New game".into(),
+            Item::Close => "Close".into(),
+            Item::Binding(action) => action.name(),
+            Item::ResetBindings => "Reset".into(),
+            Item::Back => "Back".into(),
+        }
+    }
+}
+
+/// The items of the page with the areas they take up.
+fn layout(page: Page) -> Vec<(Item, Rect)> {
+    let panel = page.panel();
+    let top = panel.y + PADDING + TITLE_FONT_SIZE + PADDING;
+
+    match page {
+        Page::Settings => {
+            const ROW_HEIGHT: f32 = 30.;
+
+            let settings = [
+                Item::AnimationSpeed,
+                Item::Easing,
+                Item::QueueMoves,
+                Item::FastMode,
+                Item::Scramble,
+                Item::BoardSize,
+                Item::Ruleset,
+                Item::Renderer,
+                Item::Theme,
+                Item::ColorblindMode,
+                Item::Volume,
+                Item::Bindings,
+            ];
+            let rows = settings.len() as f32;
+            let settings = settings.into_iter().enumerate().map(|(i, item)| {
+                let y = top + i as f32 * ROW_HEIGHT;
+                let row = Rect::new(panel.x, y, panel.w, ROW_HEIGHT);
+                (item, row)
+            });
+
+            let bottom = top + rows * ROW_HEIGHT;
+            let half_width = panel.w / 2.;
+            let buttons = [
+                (
+                    Item::NewGame,
+                    Rect::new(panel.x, bottom, half_width, ROW_HEIGHT),
+                ),
+                (
+                    Item::Close,
+                    Rect::new(panel.x + half_width, bottom, half_width, ROW_HEIGHT),
+                ),
+            ];
+
+            settings.chain(buttons).collect()
+        }
+        Page::Bindings => {
+            const ROW_HEIGHT: f32 = 24.;
+
+            let actions = KeyAction::all();
+            let rows_per_column = actions.len().div_ceil(2);
+            let column_width = panel.w / 2.;
+
+            let bindings = actions.into_iter().enumerate().map(|(i, action)| {
+                let column = (i / rows_per_column) as f32;
+                let row = (i % rows_per_column) as f32;
+                let area = Rect::new(
+                    panel.x + column * column_width,
+                    top + row * ROW_HEIGHT,
+                    column_width,
+                    ROW_HEIGHT,
+                );
+                (Item::Binding(action), area)
+            });
+
+            let bottom = panel.bottom() - PADDING - ROW_HEIGHT;
+            let buttons = [
+                (
+                    Item::ResetBindings,
+                    Rect::new(panel.x, bottom, column_width, ROW_HEIGHT),
+                ),
+                (
+                    Item::Back,
+                    Rect::new(panel.x + column_width, bottom, column_width, ROW_HEIGHT),
+                ),
+            ];
+
+            bindings.chain(buttons).collect()
+        }
+    }
+}
+
+fn item_at(page: Page, x: f32, y: f32) -> Option<Item> {
+    layout(page)
+        .into_iter()
+        .find(|(_, area)| area.contains(Vec2::new(x, y)))
+        .map(|(item, _)| item)
+}
+
+/// What the menu did that the game has to react to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MenuEvent {
+    SettingsChanged,
+    KeyMapChanged,
+    /// The player gave up the game to start a new one.
+    NewGame,
+    Closed,
+}
+
+#[derive(Debug)]
+pub struct Menu {
+    page: Page,
+    hovered: Option<Item>,
+    /// The action that the next pressed key will be bound to.
+    rebinding: Option<KeyAction>,
+    /// The themes the theme setting goes through, see [theme::choices].
+    themes: Vec<Option<String>>,
+    /// The target board of the rules when the menu was made, which the rules
+    /// setting goes back to after the presets.
+    target: Option<BoardState>,
+}
+
+impl Menu {
+    pub fn new(settings: &Settings) -> Self {
+        Self {
+            page: Page::Settings,
+            hovered: None,
+            rebinding: None,
+            themes: theme::choices(settings.theme.as_deref()),
+            target: match &settings.ruleset {
+                Ruleset::Target(target) => Some(target.clone()),
+                _ => None,
+            },
+        }
+    }
+
+    pub fn handle_event(
+        &mut self,
+        event: &MouseEvent,
+        settings: &mut Settings,
+        key_map: &mut KeyMap,
+    ) -> Option<MenuEvent> {
+        match *event {
+            MouseEvent::Moved { x, y } => {
+                self.hovered = item_at(self.page, x, y);
+                None
+            }
+            MouseEvent::Pressed { x, y } => {
+                self.rebinding = None;
+                let item = item_at(self.page, x, y)?;
+                self.select(item, settings, key_map)
+            }
+            MouseEvent::Exited => {
+                self.hovered = None;
+                None
+            }
+            _ => None,
+        }
+    }
+
+    pub fn handle_key(&mut self, key: KeyCode, key_map: &mut KeyMap) -> Option<MenuEvent> {
+        if let Some(action) = self.rebinding {
+            if key == MENU_KEY {
+                self.rebinding = None;
+                return None;
+            }
+
+            // Keep waiting if the key can't be bound
+            if !key_map.rebind(action, key) {
+                return None;
+            }
+
+            self.rebinding = None;
+            return Some(MenuEvent::KeyMapChanged);
+        }
+
+        match (key, self.page) {
+            (MENU_KEY, Page::Settings) => Some(MenuEvent::Closed),
+            (MENU_KEY, Page::Bindings) => {
+                self.show(Page::Settings);
+                None
+            }
+            _ => None,
+        }
+    }
+
+    fn show(&mut self, page: Page) {
+        self.page = page;
+        self.hovered = None;
+    }
+
+    fn select(
+        &mut self,
+        item: Item,
+        settings: &mut Settings,
+        key_map: &mut KeyMap,
+    ) -> Option<MenuEvent> {
+        match item {
+            Item::AnimationSpeed => settings.animation_speed = settings.animation_speed.next(),
+            Item::Easing => settings.easing = settings.easing.next(),
+            Item::QueueMoves => settings.queue_moves = !settings.queue_moves,
+            Item::FastMode => settings.fast_mode = !settings.fast_mode,
+            Item::Scramble => settings.scramble = settings.scramble.next(),
+            Item::BoardSize => settings.board_size = settings.board_size.next(),
+            Item::Ruleset => settings.ruleset = settings.ruleset.next(self.target.as_ref()),
+            Item::Renderer => settings.renderer = settings.renderer.toggled(),
+            Item::Theme => {
+                let index = self
+                    .themes
+                    .iter()
+                    .position(|theme| *theme == settings.theme);
+                let next = index.map_or(0, |index| (index + 1) % self.themes.len());
+                settings.theme = self.themes[next].clone();
+            }
+            Item::ColorblindMode => settings.colorblind_mode = !settings.colorblind_mode,
+            Item::Volume => settings.volume = settings.volume.next(),
+            Item::Bindings => {
+                self.show(Page::Bindings);
+                return None;
+            }
+            Item::NewGame => return Some(MenuEvent::NewGame),
+            Item::Close => return Some(MenuEvent::Closed),
+            Item::Binding(action) => {
+                self.rebinding = Some(action);
+                return None;
+            }
+            Item::ResetBindings => {
+                *key_map = KeyMap::default();
+                return Some(MenuEvent::KeyMapChanged);
+            }
+            Item::Back => {
+                self.show(Page::Settings);
+                return None;
+            }
+        }
+
+        Some(MenuEvent::SettingsChanged)
+    }
+
+    fn value(&self, item: Item, settings: &Settings, key_map: &KeyMap) -> String {
+        let on_off = |on: bool| if on { "On" } else { "Off" }.to_string();
+
+        match item {
+            Item::AnimationSpeed => match settings.animation_speed {
+                AnimationSpeed::Slow => "Slow".into(),
+                AnimationSpeed::Normal => "Normal".into(),
+                AnimationSpeed::Fast => "Fast".into(),
+            },
+            Item::Easing => match settings.easing {
+                Easing::Linear => "Linear".into(),
+                Easing::Quad => "Quadratic".into(),
+                Easing::Cubic => "Cubic".into(),
+                Easing::Back => "Back".into(),
+                Easing::Elastic => "Elastic".into(),
+                Easing::Bounce => "Bounce".into(),
+            },
+            Item::Scramble => match settings.scramble {
+                Scramble::RandomState => "Random state".into(),
+                Scramble::Moves(count) => format!("{} moves", count),
+            },
+            Item::BoardSize => {
+                let squares = settings.board_size.square_size();
+                format!("{}x{}", squares, squares)
+            }
+            Item::Ruleset => match settings.ruleset {
+                Ruleset::Standard => "Standard".into(),
+                Ruleset::RotationsOnly => "Rotations only".into(),
+                Ruleset::SlidesOnly => "Slides only".into(),
+                Ruleset::Checkerboard => "Checkerboard".into(),
+                Ruleset::Target(_) => "Target image".into(),
+            },
+            Item::Renderer => match settings.renderer {
+                Renderer::Bitmap => "Images".into(),
+                Renderer::Vector => "Shapes".into(),
+            },
+            Item::Theme => match &settings.theme {
+                Some(directory) => theme::display_name(directory).into(),
+                None => "Default".into(),
+            },
+            Item::QueueMoves => on_off(settings.queue_moves),
+            Item::FastMode => on_off(settings.fast_mode),
+            Item::ColorblindMode => on_off(settings.colorblind_mode),
+            Item::Volume if settings.volume == Volume::OFF => "Off".into(),
+            Item::Volume => format!("{}%", settings.volume.percent()),
+            Item::Binding(action) if self.rebinding == Some(action) => "Press a key".into(),
+            Item::Binding(action) => {
+                let keys: Vec<_> = key_map.keys(action).into_iter().map(key_name).collect();
+                keys.join(", ")
+            }
+            Item::Bindings | Item::NewGame | Item::Close | Item::ResetBindings | Item::Back => {
+                String::new()
+            }
+        }
+    }
+
+    pub fn draw(&self, settings: &Settings, key_map: &KeyMap) {
+        let panel = self.page.panel();
+        let font_size = self.page.font_size();
+
+        draw_rectangle(0., 0., BOARD_SIZE, BOARD_SIZE, OVERLAY_COLOR);
+        draw_rectangle(panel.x, panel.y, panel.w, panel.h, PANEL_COLOR);
+        draw_rectangle_lines(panel.x, panel.y, panel.w, panel.h, 3., BORDER_COLOR);
+
+        let title = self.page.title();
+        let dimensions = measure_text(title, None, TITLE_FONT_SIZE as u16, 1.0);
+        draw_text(
+            title,
+            panel.center().x - dimensions.width / 2.,
+            panel.y + PADDING + dimensions.offset_y,
+            TITLE_FONT_SIZE,
+            TEXT_COLOR,
+        );
+
+        for (item, area) in layout(self.page) {
+            let rebinding = matches!(item, Item::Binding(action) if self.rebinding == Some(action));
+            if self.hovered == Some(item) || rebinding {
+                draw_rectangle(area.x, area.y, area.w, area.h, HOVER_COLOR);
+            }
+
+            let baseline = area.y + (area.h + font_size * 0.5) / 2.;
+            let label = item.label();
+            let value = self.value(item, settings, key_map);
+
+            if value.is_empty() {
+                let dimensions = measure_text(&label, None, font_size as u16, 1.0);
+                draw_text(
+                    &label,
+                    area.center().x - dimensions.width / 2.,
+                    baseline,
+                    font_size,
+                    TEXT_COLOR,
+                );
+                continue;
+            }
+
+            draw_text(&label, area.x + PADDING, baseline, font_size, TEXT_COLOR);
+
+            let dimensions = measure_text(&value, None, font_size as u16, 1.0);
+            draw_text(
+                &value,
+                area.right() - PADDING - dimensions.width,
+                baseline,
+                font_size,
+                VALUE_COLOR,
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ruleset::checkerboard, BoardSize};
+    use test_case::test_case;
+
+    fn center_of(page: Page, item: Item) -> (f32, f32) {
+        let (_, area) = layout(page)
+            .into_iter()
+            .find(|(laid_out, _)| *laid_out == item)
+            .unwrap();
+        area.center().into()
+    }
+
+    fn click(menu: &mut Menu, item: Item, settings: &mut Settings, key_map: &mut KeyMap) {
+        let (x, y) = center_of(menu.page, item);
+        menu.handle_event(&MouseEvent::Pressed { x, y }, settings, key_map);
+    }
+
+    #[test_case(Page::Settings)]
+    #[test_case(Page::Bindings)]
+    fn items_are_inside_the_panel_without_overlapping(page: Page) {
+        let items = layout(page);
+        let panel = page.panel();
+
+        for (i, (_, area)) in items.iter().enumerate() {
+            assert!(panel.contains(area.point()) && panel.contains(area.point() + area.size()));
+
+            for (_, other) in &items[i + 1..] {
+                assert!(area
+                    .intersect(*other)
+                    .is_none_or(|overlap| overlap.w * overlap.h < 0.01));
+            }
+        }
+    }
+
+    #[test]
+    fn clicking_a_setting_changes_it() {
+        let mut menu = Menu::new(&Settings::default());
+        let mut settings = Settings::default();
+        let mut key_map = KeyMap::default();
+        let (x, y) = center_of(Page::Settings, Item::ColorblindMode);
+
+        let event = menu.handle_event(&MouseEvent::Pressed { x, y }, &mut settings, &mut key_map);
+
+        assert_eq!(event, Some(MenuEvent::SettingsChanged));
+        assert!(settings.colorblind_mode);
+    }
+
+    #[test]
+    fn theme_goes_through_the_choices_and_back() {
+        let mut settings = Settings {
+            theme: Some("elsewhere/light".into()),
+            ..Settings::default()
+        };
+        let mut menu = Menu::new(&settings);
+        let mut key_map = KeyMap::default();
+
+        click(&mut menu, Item::Theme, &mut settings, &mut key_map);
+        assert_eq!(settings.theme, None);
+
+        for _ in 1..menu.themes.len() {
+            click(&mut menu, Item::Theme, &mut settings, &mut key_map);
+        }
+        assert_eq!(settings.theme.as_deref(), Some("elsewhere/light"));
+    }
+
+    #[test]
+    fn rules_go_back_to_the_configured_target() {
+        let target = Ruleset::Target(checkerboard(BoardSize::STANDARD));
+        let mut settings = Settings {
+            ruleset: target.clone(),
+            ..Settings::default()
+        };
+        let mut menu = Menu::new(&settings);
+        let mut key_map = KeyMap::default();
+
+        click(&mut menu, Item::Ruleset, &mut settings, &mut key_map);
+        assert_eq!(settings.ruleset, Ruleset::Standard);
+
+        for _ in 0..4 {
+            click(&mut menu, Item::Ruleset, &mut settings, &mut key_map);
+        }
+        assert_eq!(settings.ruleset, target);
+    }
+
+    #[test]
+    fn new_game_is_asked_for() {
+        let mut menu = Menu::new(&Settings::default());
+        let mut settings = Settings::default();
+        let mut key_map = KeyMap::default();
+        let (x, y) = center_of(Page::Settings, Item::NewGame);
+
+        let event = menu.handle_event(&MouseEvent::Pressed { x, y }, &mut settings, &mut key_map);
+
+        assert_eq!(event, Some(MenuEvent::NewGame));
+    }
+
+    #[test]
+    fn clicking_outside_the_items_does_nothing() {
+        let mut menu = Menu::new(&Settings::default());
+        let mut settings = Settings::default();
+        let mut key_map = KeyMap::default();
+
+        let event = menu.handle_event(
+            &MouseEvent::Pressed { x: 1., y: 1. },
+            &mut settings,
+            &mut key_map,
+        );
+
+        assert_eq!(event, None);
+        assert_eq!(settings, Settings::default());
+    }
+
+    #[test]
+    fn clicked_action_is_bound_to_the_next_key() {
+        let mut menu = Menu::new(&Settings::default());
+        let mut settings = Settings::default();
+        let mut key_map = KeyMap::default();
+
+        click(&mut menu, Item::Bindings, &mut settings, &mut key_map);
+        click(
+            &mut menu,
+            Item::Binding(KeyAction::Hint),
+            &mut settings,
+            &mut key_map,
+        );
+
+        assert_eq!(menu.handle_key(KeyCode::F13, &mut key_map), None);
+        assert_eq!(
+            menu.handle_key(KeyCode::G, &mut key_map),
+            Some(MenuEvent::KeyMapChanged)
+        );
+        assert_eq!(key_map.keys(KeyAction::Hint), vec![KeyCode::G]);
+        assert_eq!(menu.rebinding, None);
+    }
+
+    #[test]
+    fn menu_key_goes_back_and_then_closes() {
+        let mut menu = Menu::new(&Settings::default());
+        let mut settings = Settings::default();
+        let mut key_map = KeyMap::default();
+        click(&mut menu, Item::Bindings, &mut settings, &mut key_map);
+        click(
+            &mut menu,
+            Item::Binding(KeyAction::Hint),
+            &mut settings,
+            &mut key_map,
+        );
+
+        // Cancels the rebinding, goes back to the settings and closes the menu
+        assert_eq!(menu.handle_key(MENU_KEY, &mut key_map), None);
+        assert_eq!(menu.handle_key(MENU_KEY, &mut key_map), None);
+        assert_eq!(menu.page, Page::Settings);
+        assert_eq!(
+            menu.handle_key(MENU_KEY, &mut key_map),
+            Some(MenuEvent::Closed)
+        );
+        assert_eq!(key_map, KeyMap::default());
+    }
+}
@@ -0,0 +1,346 @@
+//! Textual notation for manipulations.
+//!
+//! Every manipulation is written as a letter followed by its row, column or
+//! ring: `L`/`R` slide a row left/right, `U`/`D` slide a column up/down and
+//! `C`/`A` rotate a ring clockwise/anticlockwise, e.g. `L3`, `U5` or `C0`.
+//! A move can be followed by `'` to invert it and by `x<count>` to repeat it,
+//! so `C1'` is the same as `A1` and `R4x2` is the same as `R4 R4`. A move can
+//! be repeated up to [MAX_REPETITIONS] times. Moves in a sequence are
+//! separated by whitespace or commas.
+//!
+//! Rows and columns are numbered across the whole board, so the indices
+//! depend on the board size, see [crate::BoardSize]. A move is valid if it
+//! exists on a board of any size.
+
+use std::{error::Error, fmt::Display, str::FromStr};
+
+use crate::{BoardSize, Manipulation};
+
+/// Highest repetition count of a move, so that a short text can't expand into
+/// more moves than fit in memory.
+pub const MAX_REPETITIONS: usize = 1000;
+
+#[derive(Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum NotationErrorKind {
+    UnknownMove(char),
+    MissingIndex,
+    /// The row, column or ring as written, which may be too large for any
+    /// number type.
+    InvalidIndex(String),
+    InvalidRepetition,
+    UnexpectedCharacter(char),
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct NotationError {
+    /// Byte offset of the offending character in the parsed string.
+    pub offset: usize,
+    pub kind: NotationErrorKind,
+}
+
+impl Display for NotationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        use NotationErrorKind::*;
+
+        match &self.kind {
+            UnknownMove(c) => write!(f, "Unknown move '{}'", c)?,
+            MissingIndex => write!(f, "Move is missing the row, column or ring")?,
+            InvalidIndex(index) => write!(f, "Invalid row, column or ring {}", index)?,
+            InvalidRepetition => write!(f, "Invalid repetition count")?,
+            UnexpectedCharacter(c) => write!(f, "Unexpected character '{}'", c)?,
+        }
+
+        write!(f, " at byte {}", self.offset)
+    }
+}
+
+impl Error for NotationError {}
+
+impl Display for Manipulation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        use Manipulation::*;
+
+        match self {
+            SlideLeft(row) => write!(f, "L{}", row),
+            SlideRight(row) => write!(f, "R{}", row),
+            SlideUp(col) => write!(f, "U{}", col),
+            SlideDown(col) => write!(f, "D{}", col),
+            RotateClockwise(ring) => write!(f, "C{}", ring),
+            RotateAnticlockwise(ring) => write!(f, "A{}", ring),
+        }
+    }
+}
+
+impl FromStr for Manipulation {
+    type Err = NotationError;
+
+    /// Parse a single move. The inverse suffix is allowed, repetition is not.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parser = Parser {
+            input: s,
+            offset: 0,
+        };
+        let (manipulation, _) = parser.parse_move(false)?;
+
+        if let Some(c) = parser.peek() {
+            return Err(parser.error(NotationErrorKind::UnexpectedCharacter(c)));
+        }
+
+        Ok(manipulation)
+    }
+}
+
+/// Parse a whole sequence of moves, with repetitions expanded.
+pub fn parse_sequence(s: &str) -> Result<Vec<Manipulation>, NotationError> {
+    let mut parser = Parser {
+        input: s,
+        offset: 0,
+    };
+    let mut manipulations = vec![];
+
+    loop {
+        parser.skip_separators();
+
+        if parser.peek().is_none() {
+            break;
+        }
+
+        let (manipulation, count) = parser.parse_move(true)?;
+        manipulations.extend(std::iter::repeat_n(manipulation, count));
+
+        match parser.peek() {
+            None => break,
+            Some(c) if c.is_whitespace() || c == ',' => (),
+            Some(c) => return Err(parser.error(NotationErrorKind::UnexpectedCharacter(c))),
+        }
+    }
+
+    Ok(manipulations)
+}
+
+/// Write a sequence of moves, with consecutive repeated moves collapsed.
+pub fn format_sequence(manipulations: &[Manipulation]) -> String {
+    manipulations
+        .chunk_by(|a, b| a == b)
+        .flat_map(|run| run.chunks(MAX_REPETITIONS))
+        .map(|run| match run.len() {
+            1 => run[0].to_string(),
+            count => format!("{}x{}", run[0], count),
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+struct Parser<'a> {
+    input: &'a str,
+    offset: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn peek(&self) -> Option<char> {
+        self.input[self.offset..].chars().next()
+    }
+
+    fn advance(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.offset += c.len_utf8();
+        Some(c)
+    }
+
+    fn error(&self, kind: NotationErrorKind) -> NotationError {
+        NotationError {
+            offset: self.offset,
+            kind,
+        }
+    }
+
+    fn skip_separators(&mut self) {
+        while self.peek().is_some_and(|c| c.is_whitespace() || c == ',') {
+            self.advance();
+        }
+    }
+
+    /// The digits at the offset, or `None` if there are none.
+    fn parse_digits(&mut self) -> Option<&'a str> {
+        let start = self.offset;
+        while self.peek().is_some_and(|c| c.is_ascii_digit()) {
+            self.advance();
+        }
+
+        Some(&self.input[start..self.offset]).filter(|digits| !digits.is_empty())
+    }
+
+    /// Parse a move and its repetition count, which is an error unless
+    /// `allow_repetition` is set.
+    fn parse_move(
+        &mut self,
+        allow_repetition: bool,
+    ) -> Result<(Manipulation, usize), NotationError> {
+        use Manipulation::*;
+
+        let letter_offset = self.offset;
+        let constructor = match self.advance() {
+            Some('L') => SlideLeft,
+            Some('R') => SlideRight,
+            Some('U') => SlideUp,
+            Some('D') => SlideDown,
+            Some('C') => RotateClockwise,
+            Some('A') => RotateAnticlockwise,
+            Some(c) => {
+                return Err(NotationError {
+                    offset: letter_offset,
+                    kind: NotationErrorKind::UnknownMove(c),
+                })
+            }
+            None => return Err(self.error(NotationErrorKind::MissingIndex)),
+        };
+
+        let index_offset = self.offset;
+        let index = self
+            .parse_digits()
+            .ok_or_else(|| self.error(NotationErrorKind::MissingIndex))?;
+
+        let mut manipulation = index
+            .parse()
+            .map(constructor)
+            .ok()
+            .filter(|manipulation| {
+                BoardSize::ALL
+                    .into_iter()
+                    .any(|size| Manipulation::all(size).contains(manipulation))
+            })
+            .ok_or(NotationError {
+                offset: index_offset,
+                kind: NotationErrorKind::InvalidIndex(index.into()),
+            })?;
+
+        if self.peek() == Some('\'') {
+            self.advance();
+            manipulation = manipulation.inverse();
+        }
+
+        let mut count = 1;
+        if self.peek() == Some('x') {
+            self.advance();
+            let count_offset = self.offset;
+
+            count = self
+                .parse_digits()
+                .and_then(|count| count.parse().ok())
+                .filter(|count| allow_repetition && (1..=MAX_REPETITIONS).contains(count))
+                .ok_or(NotationError {
+                    offset: count_offset,
+                    kind: NotationErrorKind::InvalidRepetition,
+                })?;
+        }
+
+        Ok((manipulation, count))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_case::test_case;
+    use Manipulation::*;
+
+    #[test]
+    fn every_manipulation_round_trips() {
+        for size in BoardSize::ALL {
+            for manipulation in Manipulation::all(size) {
+                let text = manipulation.to_string();
+
+                assert_eq!(text.parse::<Manipulation>(), Ok(manipulation));
+            }
+        }
+    }
+
+    #[test_case("L3", SlideLeft(3))]
+    #[test_case("R4", SlideRight(4))]
+    #[test_case("U5", SlideUp(5))]
+    #[test_case("D3", SlideDown(3))]
+    #[test_case("C0", RotateClockwise(0))]
+    #[test_case("A2", RotateAnticlockwise(2))]
+    #[test_case("C4", RotateClockwise(4); "ring of a larger board")]
+    #[test_case("U9", SlideUp(9); "column of a larger board")]
+    #[test_case("C1'", RotateAnticlockwise(1); "inverted rotation")]
+    #[test_case("L4'", SlideRight(4); "inverted slide")]
+    fn single_move_is_parsed(text: &str, expected: Manipulation) {
+        assert_eq!(text.parse::<Manipulation>(), Ok(expected));
+    }
+
+    #[test]
+    fn sequence_is_parsed_with_repetitions() {
+        let sequence = parse_sequence(" L3 R4x2,C1'  A0'x3 ").unwrap();
+
+        assert_eq!(
+            sequence,
+            vec![
+                SlideLeft(3),
+                SlideRight(4),
+                SlideRight(4),
+                RotateAnticlockwise(1),
+                RotateClockwise(0),
+                RotateClockwise(0),
+                RotateClockwise(0),
+            ]
+        );
+    }
+
+    #[test]
+    fn empty_sequence_is_valid() {
+        assert_eq!(parse_sequence("  "), Ok(vec![]));
+    }
+
+    #[test]
+    fn sequence_round_trips() {
+        let sequence = vec![
+            SlideUp(5),
+            SlideUp(5),
+            RotateClockwise(2),
+            SlideDown(3),
+            RotateClockwise(2),
+            RotateClockwise(2),
+            RotateClockwise(2),
+        ];
+
+        let text = format_sequence(&sequence);
+
+        assert_eq!(text, "U5x2 C2 D3 C2x3");
+        assert_eq!(parse_sequence(&text), Ok(sequence));
+    }
+
+    #[test_case("L3 X4", 3, NotationErrorKind::UnknownMove('X'); "unknown move")]
+    #[test_case("L3 R", 4, NotationErrorKind::MissingIndex; "missing index")]
+    #[test_case("C0 C5", 4, NotationErrorKind::InvalidIndex("5".into()); "invalid ring")]
+    #[test_case("L3 L0", 4, NotationErrorKind::InvalidIndex("0".into()); "invalid row")]
+    #[test_case("L3 L99999999999", 4, NotationErrorKind::InvalidIndex("99999999999".into()); "index too large for any board")]
+    #[test_case("L3 U4x", 6, NotationErrorKind::InvalidRepetition; "missing count")]
+    #[test_case("R4x0", 3, NotationErrorKind::InvalidRepetition; "zero count")]
+    #[test_case("L3 R4x1001", 6, NotationErrorKind::InvalidRepetition; "count above the limit")]
+    #[test_case("R4x4000000000", 3, NotationErrorKind::InvalidRepetition; "count in the billions")]
+    #[test_case("R4C1", 2, NotationErrorKind::UnexpectedCharacter('C'); "missing separator")]
+    fn sequence_error_reports_offset(text: &str, offset: usize, kind: NotationErrorKind) {
+        assert_eq!(parse_sequence(text), Err(NotationError { offset, kind }));
+    }
+
+    #[test]
+    fn highest_repetition_count_is_allowed() {
+        let sequence = parse_sequence(&format!("C2x{}", MAX_REPETITIONS)).unwrap();
+
+        assert_eq!(sequence.len(), MAX_REPETITIONS);
+    }
+
+    #[test]
+    fn single_move_rejects_repetition_at_the_same_offset_as_a_sequence() {
+        let result = "R4x2".parse::<Manipulation>();
+        let expected = NotationError {
+            offset: 3,
+            kind: NotationErrorKind::InvalidRepetition,
+        };
+
+        assert_eq!(result, Err(expected));
+        assert_eq!(parse_sequence("R4x0").unwrap_err().offset, 3);
+    }
+}
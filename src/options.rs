@@ -0,0 +1,65 @@
+/// Options given when starting the game. On desktop they come from the command
+/// line, e.g. `--seed=1234`, and on the web from the URL query, e.g. `?seed=1234`.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Options {
+    pub seed: Option<u64>,
+    /// Directory of the theme to use instead of the embedded art.
+    pub theme: Option<String>,
+}
+
+impl Options {
+    /// Parse the program parameters, with the program path as the first one.
+    pub fn parse(parameters: &[String]) -> Self {
+        let mut options = Options::default();
+        let mut parameters = parameters.iter().skip(1);
+
+        while let Some(parameter) = parameters.next() {
+            let (name, value) = match parameter.split_once('=') {
+                Some((name, value)) => (name, Some(value.trim_matches('"'))),
+                None => (parameter.as_str(), None),
+            };
+
+            match name {
+                "--seed" | "-s" => {
+                    let value = value.or_else(|| parameters.next().map(String::as_str));
+                    options.seed = value.and_then(|value| value.parse().ok());
+                }
+                "--theme" | "-t" => {
+                    let value = value.or_else(|| parameters.next().map(String::as_str));
+                    options.theme = value.map(str::to_string);
+                }
+                _ => println!("Ignoring unknown parameter {}", parameter),
+            }
+        }
+
+        options
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_case::test_case;
+
+    #[test_case(&["rubicross"], None; "no parameters")]
+    #[test_case(&["rubicross", "--seed=1234"], Some(1234); "seed with equals sign")]
+    #[test_case(&["rubicross", "--seed", "1234"], Some(1234); "seed as separate parameter")]
+    #[test_case(&["index.html", "--seed=\"99\""], Some(99); "quoted seed")]
+    #[test_case(&["rubicross", "-s=5"], Some(5); "short name")]
+    #[test_case(&["rubicross", "--seed=abc"], None; "invalid seed")]
+    fn seed_is_parsed(parameters: &[&str], expected: Option<u64>) {
+        let parameters: Vec<String> = parameters.iter().map(|p| p.to_string()).collect();
+
+        assert_eq!(Options::parse(&parameters).seed, expected);
+    }
+
+    #[test_case(&["rubicross"], None; "no parameters")]
+    #[test_case(&["rubicross", "--theme=themes/dark"], Some("themes/dark"); "theme with equals sign")]
+    #[test_case(&["rubicross", "-t", "dark", "--seed=3"], Some("dark"); "theme with other options")]
+    #[test_case(&["index.html", "--theme=\"my theme\""], Some("my theme"); "quoted theme")]
+    fn theme_is_parsed(parameters: &[&str], expected: Option<&str>) {
+        let parameters: Vec<String> = parameters.iter().map(|p| p.to_string()).collect();
+
+        assert_eq!(Options::parse(&parameters).theme.as_deref(), expected);
+    }
+}
@@ -0,0 +1,491 @@
+use std::{error::Error, fmt::Display, str::FromStr};
+
+use super::position::{self, BoardSize, PieceError, Position, Square};
+
+#[derive(Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum StateError {
+    InvalidLength(usize),
+    InvalidColor {
+        offset: usize,
+        symbol: char,
+    },
+    InvalidColorCount {
+        color: Color,
+        count: usize,
+        expected: usize,
+    },
+}
+
+impl Display for StateError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            StateError::InvalidLength(length) => {
+                write!(f, "No board has {} pieces", length)
+            }
+            StateError::InvalidColor { offset, symbol } => {
+                write!(f, "Invalid color '{}' at byte {}", symbol, offset)
+            }
+            StateError::InvalidColorCount {
+                color,
+                count,
+                expected,
+            } => write!(
+                f,
+                "Expected {} {:?} pieces, found {}",
+                expected, color, count
+            ),
+        }
+    }
+}
+
+impl Error for StateError {}
+
+#[derive(PartialEq, Eq, Hash, Copy, Clone, Debug)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "lowercase")
+)]
+pub enum Color {
+    Yellow,
+    Blue,
+    Red,
+    Purple,
+    Green,
+}
+
+impl Color {
+    pub const ALL: [Color; 5] = [
+        Color::Yellow,
+        Color::Blue,
+        Color::Red,
+        Color::Purple,
+        Color::Green,
+    ];
+
+    /// Character representing the color in the canonical board string.
+    pub fn symbol(&self) -> char {
+        match self {
+            Color::Yellow => 'Y',
+            Color::Blue => 'B',
+            Color::Red => 'R',
+            Color::Purple => 'P',
+            Color::Green => 'G',
+        }
+    }
+
+    pub fn from_symbol(symbol: char) -> Option<Color> {
+        Color::ALL
+            .into_iter()
+            .find(|color| color.symbol() == symbol)
+    }
+}
+
+#[derive(PartialEq, Eq, Hash, Copy, Clone, Debug)]
+pub enum Manipulation {
+    RotateClockwise(u8),
+    RotateAnticlockwise(u8),
+    SlideLeft(u8),
+    SlideRight(u8),
+    SlideUp(u8),
+    SlideDown(u8),
+}
+
+impl Manipulation {
+    /// All the manipulations that can be made on a board of the size. Every
+    /// manipulation is immediately followed by its inverse.
+    pub fn all(size: BoardSize) -> Vec<Manipulation> {
+        use Manipulation::*;
+
+        let rotations = size
+            .rings()
+            .flat_map(|ring| [RotateClockwise(ring), RotateAnticlockwise(ring)]);
+        let row_slides = size
+            .middle_lines()
+            .flat_map(|row| [SlideLeft(row), SlideRight(row)]);
+        let column_slides = size
+            .middle_lines()
+            .flat_map(|col| [SlideUp(col), SlideDown(col)]);
+
+        rotations.chain(row_slides).chain(column_slides).collect()
+    }
+
+    /// The manipulation that undoes this one.
+    pub fn inverse(&self) -> Manipulation {
+        use Manipulation::*;
+
+        match *self {
+            RotateClockwise(ring) => RotateAnticlockwise(ring),
+            RotateAnticlockwise(ring) => RotateClockwise(ring),
+            SlideLeft(row) => SlideRight(row),
+            SlideRight(row) => SlideLeft(row),
+            SlideUp(col) => SlideDown(col),
+            SlideDown(col) => SlideUp(col),
+        }
+    }
+
+    /// Whether the piece at the given position is moved by this manipulation.
+    pub fn affects(&self, position: &Position) -> bool {
+        use Manipulation::*;
+
+        match *self {
+            RotateClockwise(ring) | RotateAnticlockwise(ring) => position.ring() == Some(ring),
+            SlideLeft(row) | SlideRight(row) => position.row() == row,
+            SlideUp(col) | SlideDown(col) => position.col() == col,
+        }
+    }
+
+    /// The movement that every affected piece makes.
+    pub fn piece_manipulation(&self) -> position::Manipulation {
+        use Manipulation::*;
+
+        match self {
+            RotateClockwise(_) => position::Manipulation::RotateClockwise,
+            RotateAnticlockwise(_) => position::Manipulation::RotateAnticlockwise,
+            SlideLeft(_) => position::Manipulation::SlideLeft,
+            SlideRight(_) => position::Manipulation::SlideRight,
+            SlideUp(_) => position::Manipulation::SlideUp,
+            SlideDown(_) => position::Manipulation::SlideDown,
+        }
+    }
+}
+
+/// Colors of all the pieces on the board, independent of any rendering.
+///
+/// The canonical string form has one [Color::symbol] per piece, in the
+/// row-major order of [Position::all]. Every board size has a different
+/// number of pieces, so the size follows from the length.
+#[derive(PartialEq, Eq, Hash, Clone, Debug)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(try_from = "Vec<Color>", into = "Vec<Color>")
+)]
+pub struct BoardState {
+    size: BoardSize,
+    cells: Vec<Color>,
+}
+
+impl Default for BoardState {
+    fn default() -> Self {
+        Self::new(BoardSize::STANDARD)
+    }
+}
+
+impl BoardState {
+    /// Create a solved board with the same layout as at the start of the game.
+    pub fn new(size: BoardSize) -> Self {
+        let cells = Position::all(size)
+            .map(|position| match position.square() {
+                Square::North => Color::Green,
+                Square::West => Color::Purple,
+                Square::Center => Color::Yellow,
+                Square::East => Color::Blue,
+                Square::South => Color::Red,
+            })
+            .collect();
+
+        Self { size, cells }
+    }
+
+    /// Create a board from the colors of all the pieces in the row-major order
+    /// of [Position::all]. There must be the same number of pieces of each color.
+    pub fn from_colors(colors: &[Color]) -> Result<Self, StateError> {
+        let size = BoardSize::ALL
+            .into_iter()
+            .find(|size| size.position_count() == colors.len())
+            .ok_or(StateError::InvalidLength(colors.len()))?;
+        let expected = colors.len() / Color::ALL.len();
+
+        for color in Color::ALL {
+            let count = colors.iter().filter(|c| **c == color).count();
+
+            if count != expected {
+                return Err(StateError::InvalidColorCount {
+                    color,
+                    count,
+                    expected,
+                });
+            }
+        }
+
+        Ok(Self {
+            size,
+            cells: colors.to_vec(),
+        })
+    }
+
+    pub fn size(&self) -> BoardSize {
+        self.size
+    }
+
+    pub fn colors(&self) -> &[Color] {
+        &self.cells
+    }
+
+    pub fn color_at(&self, position: &Position) -> Color {
+        self.cells[position.index()]
+    }
+
+    /// Iterate over all the positions and their colors in row-major order.
+    pub fn iter(&self) -> impl Iterator<Item = (Position, Color)> + '_ {
+        Position::all(self.size).zip(self.cells.iter().copied())
+    }
+
+    pub fn apply(&mut self, manipulation: Manipulation) -> Result<(), PieceError> {
+        let mut cells = self.cells.clone();
+
+        for position in Position::all(self.size).filter(|position| manipulation.affects(position)) {
+            let mut new_position = position;
+            new_position.apply_manipulation(manipulation.piece_manipulation())?;
+            cells[new_position.index()] = self.cells[position.index()];
+        }
+
+        self.cells = cells;
+        Ok(())
+    }
+
+    pub fn is_solved(&self) -> bool {
+        Square::ALL
+            .iter()
+            .all(|square| self.is_square_solved(*square))
+    }
+
+    pub fn is_square_solved(&self, square: Square) -> bool {
+        let mut colors = self
+            .iter()
+            .filter(|(position, _)| position.square() == square)
+            .map(|(_, color)| color);
+
+        let first_color = colors.next().unwrap();
+        colors.all(|color| color == first_color)
+    }
+}
+
+impl Display for BoardState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for color in &self.cells {
+            write!(f, "{}", color.symbol())?;
+        }
+
+        Ok(())
+    }
+}
+
+impl FromStr for BoardState {
+    type Err = StateError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let colors = s
+            .char_indices()
+            .map(|(offset, symbol)| {
+                Color::from_symbol(symbol).ok_or(StateError::InvalidColor { offset, symbol })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Self::from_colors(&colors)
+    }
+}
+
+impl TryFrom<Vec<Color>> for BoardState {
+    type Error = StateError;
+
+    fn try_from(colors: Vec<Color>) -> Result<Self, Self::Error> {
+        Self::from_colors(&colors)
+    }
+}
+
+impl From<BoardState> for Vec<Color> {
+    fn from(state: BoardState) -> Self {
+        state.cells
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_case::test_case;
+
+    #[test]
+    fn new_board_is_solved() {
+        let board = BoardState::default();
+
+        assert!(board.is_solved());
+    }
+
+    #[test]
+    fn new_board_has_nine_pieces_of_each_color() {
+        let board = BoardState::default();
+
+        for color in Color::ALL {
+            let count = board.iter().filter(|(_, c)| *c == color).count();
+            assert_eq!(count, 9);
+        }
+    }
+
+    #[test_case(2, 20)]
+    #[test_case(3, 45)]
+    #[test_case(4, 80)]
+    #[test_case(5, 125)]
+    fn every_board_size_has_its_own_length(square_size: u8, length: usize) {
+        let size = BoardSize::new(square_size).unwrap();
+        let mut board = BoardState::new(size);
+        board.apply(Manipulation::all(size)[0]).unwrap();
+
+        let parsed = board.to_string().parse::<BoardState>().unwrap();
+
+        assert_eq!(board.colors().len(), length);
+        assert_eq!(parsed.size(), size);
+        assert_eq!(parsed, board);
+    }
+
+    #[test_case(2)]
+    #[test_case(4)]
+    #[test_case(5)]
+    fn rotating_every_ring_of_any_board_size_moves_all_outer_squares(square_size: u8) {
+        let size = BoardSize::new(square_size).unwrap();
+        let mut board = BoardState::new(size);
+
+        for ring in size.rings() {
+            board.apply(Manipulation::RotateClockwise(ring)).unwrap();
+        }
+
+        let north = Position::new(size, 0, size.square_size()).unwrap();
+        assert_eq!(board.color_at(&north), Color::Purple);
+        assert!(board.is_square_solved(Square::North));
+        assert!(board.is_square_solved(Square::Center));
+    }
+
+    #[test]
+    fn every_manipulation_is_undone_by_its_inverse() {
+        for manipulation in Manipulation::all(BoardSize::STANDARD) {
+            let mut board = BoardState::default();
+            board.apply(Manipulation::SlideDown(4)).unwrap();
+            let before = board.clone();
+
+            board.apply(manipulation).unwrap();
+            board.apply(manipulation.inverse()).unwrap();
+
+            assert_eq!(board, before, "{:?}", manipulation);
+        }
+    }
+
+    #[test_case(Manipulation::RotateClockwise(0), 4)]
+    #[test_case(Manipulation::RotateAnticlockwise(2), 4)]
+    #[test_case(Manipulation::SlideLeft(3), 3)]
+    #[test_case(Manipulation::SlideDown(5), 3)]
+    fn manipulation_repeated_returns_to_start(manipulation: Manipulation, period: usize) {
+        let mut board = BoardState::default();
+        board.apply(Manipulation::SlideUp(3)).unwrap();
+        let before = board.clone();
+
+        for _ in 0..period {
+            board.apply(manipulation).unwrap();
+        }
+
+        assert_eq!(board, before);
+    }
+
+    #[test]
+    fn rotation_unsolves_the_outer_squares_only() {
+        let mut board = BoardState::default();
+
+        board.apply(Manipulation::RotateClockwise(1)).unwrap();
+
+        assert!(!board.is_solved());
+        assert!(board.is_square_solved(Square::Center));
+        assert!(!board.is_square_solved(Square::North));
+        assert!(!board.is_square_solved(Square::West));
+    }
+
+    #[test]
+    fn rotation_moves_colors_to_the_next_square() {
+        let mut board = BoardState::default();
+
+        board.apply(Manipulation::RotateClockwise(0)).unwrap();
+
+        let east_inner = Position::new(BoardSize::STANDARD, 4, 6).unwrap();
+        assert_eq!(board.color_at(&east_inner), Color::Green);
+    }
+
+    #[test]
+    fn sliding_outside_the_middle_fails() {
+        let mut board = BoardState::default();
+
+        let result = board.apply(Manipulation::SlideLeft(0));
+
+        assert!(matches!(result, Err(PieceError::CantSlide)));
+        assert_eq!(board, BoardState::default());
+    }
+
+    #[test]
+    fn new_board_has_the_expected_string() {
+        let board = BoardState::default();
+
+        assert_eq!(
+            board.to_string(),
+            "GGGGGGGGGPPPYYYBBBPPPYYYBBBPPPYYYBBBRRRRRRRRR"
+        );
+    }
+
+    #[test]
+    fn string_round_trips() {
+        let mut board = BoardState::default();
+        board.apply(Manipulation::RotateClockwise(2)).unwrap();
+        board.apply(Manipulation::SlideDown(4)).unwrap();
+
+        let parsed = board.to_string().parse::<BoardState>();
+
+        assert_eq!(parsed, Ok(board));
+    }
+
+    #[test_case("GGGG", StateError::InvalidLength(4); "too short")]
+    #[test_case(
+        "GGGGGGGGGPPPYYYBBBPPPYYYBBBPPPYYYBBBRRRRRRRRRR",
+        StateError::InvalidLength(46);
+        "too long"
+    )]
+    #[test_case(
+        "GGGGGGGGGPPPYYYBBBPPPYYXBBBPPPYYYBBBRRRRRRRRR",
+        StateError::InvalidColor { offset: 23, symbol: 'X' };
+        "unknown color"
+    )]
+    #[test_case(
+        "GGGGGGGGGPPPYYYBBBPPPYYYBBBPPPYYYBBBRRRRRRRRG",
+        StateError::InvalidColorCount { color: Color::Red, count: 8, expected: 9 };
+        "wrong color count"
+    )]
+    fn invalid_string_is_rejected(text: &str, expected: StateError) {
+        assert_eq!(text.parse::<BoardState>(), Err(expected));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn json_round_trips() {
+        let mut board = BoardState::default();
+        board.apply(Manipulation::SlideLeft(3)).unwrap();
+
+        let json = serde_json::to_string(&board).unwrap();
+        let parsed: BoardState = serde_json::from_str(&json).unwrap();
+
+        assert!(json.starts_with(r#"["green","green""#));
+        assert_eq!(parsed, board);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn json_with_wrong_color_count_is_rejected() {
+        let json = format!(
+            "[{}]",
+            vec![r#""red""#; BoardSize::STANDARD.position_count()].join(",")
+        );
+
+        let result = serde_json::from_str::<BoardState>(&json);
+
+        assert!(result
+            .unwrap_err()
+            .to_string()
+            .contains("Expected 9 Yellow"));
+    }
+}
@@ -1,157 +1,273 @@
-use crate::{initialization::PathMap, Piece};
+use std::collections::VecDeque;
 
-use super::position::{self, Square};
+use crate::{
+    initialization::PathMap,
+    ruleset::{self, Ruleset},
+    settings::Renderer,
+    BoardState, Easing, Manipulation, Path, Piece,
+};
 
+use super::position::Square;
+
+/// Most that [queued_animation_length] speeds up an animation.
+const MAX_QUEUE_SPEEDUP: usize = 4;
+
+/// The movements of the pieces for one manipulation.
+struct Movement<'a> {
+    /// Indices of the moved pieces with their paths.
+    paths: Vec<(usize, &'a Path)>,
+    path_pos: f64,
+    animation_length: f64,
+    easing: Easing,
+}
+
+/// Length of a queued animation that starts while `waiting` more animations
+/// are queued behind it. In fast mode the animations are sped up the more
+/// of them are waiting, so that the pieces catch up with the player.
+fn queued_animation_length(animation_length: f64, waiting: usize, fast_mode: bool) -> f64 {
+    if !fast_mode {
+        return animation_length;
+    }
+
+    animation_length / (waiting + 1).min(MAX_QUEUE_SPEEDUP) as f64
+}
+
+/// Animated view of a [BoardState]. Every manipulation is applied to the
+/// state immediately and the pieces then follow along their paths. The
+/// movements of manipulations made while their pieces are still moving are
+/// queued and played in order, unless they are set to interrupt them.
 pub struct PieceCollection<'a> {
     pub path_map: &'a PathMap,
     pub pieces: Vec<Piece<'a>>,
 
-    south_solved: bool,
-    north_solved: bool,
-    center_solved: bool,
-    east_solved: bool,
-    west_solved: bool,
-}
-
-#[derive(Copy, Clone, Debug)]
-pub enum Manipulation {
-    RotateClockwise(u8),
-    RotateAnticlockwise(u8),
-    SlideLeft(u8),
-    SlideRight(u8),
-    SlideUp(u8),
-    SlideDown(u8),
+    state: BoardState,
+    ruleset: Ruleset,
+    /// The board the ruleset wants, kept so that it isn't made for every check.
+    target: Option<BoardState>,
+    queue: VecDeque<Movement<'a>>,
+    queue_moves: bool,
+    fast_mode: bool,
 }
 
 impl<'a> PieceCollection<'a> {
-    pub fn new(path_map: &'a PathMap, pieces: Vec<Piece<'a>>) -> Self {
+    pub fn new(path_map: &'a PathMap, state: BoardState, pieces: Vec<Piece<'a>>) -> Self {
         Self {
             path_map,
             pieces,
-            south_solved: false,
-            north_solved: false,
-            center_solved: false,
-            east_solved: false,
-            west_solved: false,
+            state,
+            ruleset: Ruleset::default(),
+            target: None,
+            queue: VecDeque::new(),
+            queue_moves: true,
+            fast_mode: false,
         }
     }
 
-    pub fn apply_manipulation(&mut self, manipulation: Manipulation, animation_length: f64) {
-        let (pieces, piece_manipulation): (Vec<&mut Piece>, position::Manipulation) =
-            match manipulation {
-                Manipulation::RotateClockwise(ring) => {
-                    let filter =
-                        move |piece: &&mut Piece<'a>| piece.position().ring() == Some(ring);
-                    let pieces = self.pieces.iter_mut().filter(filter).collect();
-                    let piece_manipulation = position::Manipulation::RotateClockwise;
-                    (pieces, piece_manipulation)
-                }
-                Manipulation::RotateAnticlockwise(ring) => {
-                    let filter =
-                        move |piece: &&mut Piece<'a>| piece.position().ring() == Some(ring);
-                    let pieces = self.pieces.iter_mut().filter(filter).collect();
-                    let piece_manipulation = position::Manipulation::RotateAnticlockwise;
-                    (pieces, piece_manipulation)
-                }
-                Manipulation::SlideLeft(row) => {
-                    let filter = move |piece: &&mut Piece<'a>| piece.position().row() == row;
-                    let pieces = self.pieces.iter_mut().filter(filter).collect();
-                    let piece_manipulation = position::Manipulation::SlideLeft;
-                    (pieces, piece_manipulation)
-                }
-                Manipulation::SlideRight(row) => {
-                    let filter = move |piece: &&mut Piece<'a>| piece.position().row() == row;
-                    let pieces = self.pieces.iter_mut().filter(filter).collect();
-                    let piece_manipulation = position::Manipulation::SlideRight;
-                    (pieces, piece_manipulation)
-                }
-                Manipulation::SlideUp(col) => {
-                    let filter = move |piece: &&mut Piece<'a>| piece.position().col() == col;
-                    let pieces = self.pieces.iter_mut().filter(filter).collect();
-                    let piece_manipulation = position::Manipulation::SlideUp;
-                    (pieces, piece_manipulation)
-                }
-                Manipulation::SlideDown(col) => {
-                    let filter = move |piece: &&mut Piece<'a>| piece.position().col() == col;
-                    let pieces = self.pieces.iter_mut().filter(filter).collect();
-                    let piece_manipulation = position::Manipulation::SlideDown;
-                    (pieces, piece_manipulation)
-                }
-            };
-
-        for piece in pieces {
+    /// Whether movements of pieces that are still moving wait for them to
+    /// stop or interrupt them, see [Piece::start_moving_between].
+    pub fn set_queue_moves(&mut self, queue_moves: bool) {
+        self.queue_moves = queue_moves;
+    }
+
+    /// Speed up queued movements, see [queued_animation_length].
+    pub fn set_fast_mode(&mut self, fast_mode: bool) {
+        self.fast_mode = fast_mode;
+    }
+
+    /// Decide when the board is solved by the ruleset.
+    pub fn set_ruleset(&mut self, ruleset: Ruleset) {
+        self.target = ruleset.target(self.state.size());
+        self.ruleset = ruleset;
+    }
+
+    pub fn ruleset(&self) -> &Ruleset {
+        &self.ruleset
+    }
+
+    /// The exact board the ruleset wants, see [Ruleset::target].
+    pub fn target(&self) -> Option<&BoardState> {
+        self.target.as_ref()
+    }
+
+    pub fn state(&self) -> &BoardState {
+        &self.state
+    }
+
+    pub fn apply_manipulation(
+        &mut self,
+        manipulation: Manipulation,
+        animation_length: f64,
+        easing: Easing,
+    ) {
+        self.apply_manipulation_from(manipulation, 0.0, animation_length, easing);
+    }
+
+    /// Same as [Self::apply_manipulation], but the pieces continue from the
+    /// point of their paths they were held at by [Self::preview_manipulation].
+    pub fn apply_manipulation_from(
+        &mut self,
+        manipulation: Manipulation,
+        path_pos: f64,
+        animation_length: f64,
+        easing: Easing,
+    ) {
+        self.state.apply(manipulation).unwrap();
+
+        let mut paths = vec![];
+
+        let pieces = self
+            .pieces
+            .iter_mut()
+            .enumerate()
+            .filter(|(_, piece)| manipulation.affects(piece.position()));
+
+        for (index, piece) in pieces {
             let position_before = *piece.position();
             piece
                 .position_mut()
-                .apply_manipulation(piece_manipulation)
+                .apply_manipulation(manipulation.piece_manipulation())
                 .unwrap();
             let position_after = *piece.position();
 
-            if let Some(paths) = self.path_map.get(&(position_before, position_after)) {
-                piece.start_moving_along(paths, animation_length)
-            } else {
-                println!(
-                    "Missing path for {:?} -> {:?}",
-                    position_before, position_after
-                );
-            }
+            // initialize_paths covers every manipulation of every position
+            let path = &self.path_map[&(position_before, position_after)];
+            paths.push((index, path));
+        }
+
+        let movement = Movement {
+            paths,
+            path_pos,
+            animation_length,
+            easing,
+        };
+
+        let waits = self.queue_moves && self.is_moving(&movement);
+
+        if self.queue.is_empty() && !waits {
+            self.start(movement, animation_length);
+        } else {
+            self.queue.push_back(movement);
+        }
+    }
+
+    /// Whether any of the pieces of the movement are still moving.
+    fn is_moving(&self, movement: &Movement) -> bool {
+        movement
+            .paths
+            .iter()
+            .any(|(index, _)| self.pieces[*index].is_animating())
+    }
+
+    fn start(&mut self, movement: Movement<'a>, animation_length: f64) {
+        for (index, path) in movement.paths {
+            self.pieces[index].start_moving_between(
+                path,
+                movement.path_pos,
+                1.0,
+                animation_length,
+                movement.easing,
+            );
         }
     }
 
+    /// Hold the pieces a manipulation would move at a point of their paths,
+    /// without making the manipulation.
+    pub fn preview_manipulation(&mut self, manipulation: Manipulation, path_pos: f64) {
+        for (piece, path) in self.paths_of(manipulation) {
+            piece.hold_at(path, path_pos);
+        }
+    }
+
+    /// Return the pieces held by [Self::preview_manipulation] to where they were.
+    pub fn cancel_preview(
+        &mut self,
+        manipulation: Manipulation,
+        path_pos: f64,
+        animation_length: f64,
+        easing: Easing,
+    ) {
+        for (piece, path) in self.paths_of(manipulation) {
+            piece.start_moving_between(path, path_pos, 0.0, animation_length, easing);
+        }
+    }
+
+    /// The pieces a manipulation moves, with the paths they would move along.
+    fn paths_of(
+        &mut self,
+        manipulation: Manipulation,
+    ) -> impl Iterator<Item = (&mut Piece<'a>, &'a Path)> {
+        let path_map = self.path_map;
+
+        self.pieces
+            .iter_mut()
+            .filter(move |piece| manipulation.affects(piece.position()))
+            .filter_map(move |piece| {
+                let position_before = *piece.position();
+                let mut position_after = position_before;
+                position_after
+                    .apply_manipulation(manipulation.piece_manipulation())
+                    .ok()?;
+
+                let path = path_map.get(&(position_before, position_after))?;
+                Some((piece, path))
+            })
+    }
+
     pub fn update(&mut self) {
-        // Update the pieces
         for piece in &mut self.pieces {
             piece.update();
         }
 
-        // Check if solved status changed
-        use position::Square::*;
-        for (square, store_var) in [
-            (North, &mut self.north_solved),
-            (East, &mut self.east_solved),
-            (Center, &mut self.center_solved),
-            (West, &mut self.west_solved),
-            (South, &mut self.south_solved),
-        ] {
-            let mut square_pieces = self
-                .pieces
-                .iter()
-                .filter(|piece| piece.position().square() == square);
-
-            let first_piece = square_pieces.next().unwrap();
-            let square_solved = square_pieces.all(|piece| first_piece.has_same_color_as(piece));
-
-            if square_solved != *store_var {
-                *store_var = square_solved;
+        // Movements of different pieces can start together, but not before
+        // the ones queued ahead of them
+        while let Some(movement) = self.queue.front() {
+            if self.is_moving(movement) {
+                break;
             }
+
+            let movement = self.queue.pop_front().unwrap();
+            let animation_length = queued_animation_length(
+                movement.animation_length,
+                self.queue.len(),
+                self.fast_mode,
+            );
+            self.start(movement, animation_length);
         }
     }
 
+    /// Whether any pieces are moving or have queued movements.
     pub fn is_animating(&self) -> bool {
-        self.pieces.iter().any(|piece| piece.is_animating())
+        !self.queue.is_empty() || self.pieces.iter().any(|piece| piece.is_animating())
     }
 
     pub fn is_solved(&self) -> bool {
-        self.east_solved
-            && self.west_solved
-            && self.north_solved
-            && self.south_solved
-            && self.center_solved
+        ruleset::is_solved_for(&self.state, self.target())
     }
 
     pub fn is_square_solved(&self, square: &Square) -> bool {
-        match square {
-            Square::North => self.north_solved,
-            Square::South => self.south_solved,
-            Square::Center => self.center_solved,
-            Square::West => self.west_solved,
-            Square::East => self.east_solved,
-        }
+        ruleset::is_square_solved_for(&self.state, self.target(), *square)
     }
 
-    pub fn draw(&self) {
+    pub fn draw(&self, show_symbols: bool, renderer: Renderer) {
         for piece in &self.pieces {
-            piece.draw();
+            piece.draw(show_symbols, renderer);
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_case::test_case;
+
+    #[test_case(0, false, 0.4; "nothing waiting")]
+    #[test_case(3, false, 0.4; "fast mode off")]
+    #[test_case(0, true, 0.4; "fast mode with nothing waiting")]
+    #[test_case(1, true, 0.2; "one waiting")]
+    #[test_case(20, true, 0.1; "speedup is limited")]
+    fn queued_animations_are_sped_up_in_fast_mode(waiting: usize, fast_mode: bool, expected: f64) {
+        let length = queued_animation_length(0.4, waiting, fast_mode);
+
+        assert!((length - expected).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,163 @@
+//! Curves that map the time of an animation to how far along its path a
+//! piece is, both from 0 to 1. See <https://easings.net> for how they look.
+
+use std::f64::consts::PI;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Easing {
+    Linear,
+    /// Speeds up and slows down, the curve the pieces always used.
+    #[default]
+    Quad,
+    Cubic,
+    /// Pulls back a little at the start and overshoots at the end.
+    Back,
+    /// Overshoots and springs back into place.
+    Elastic,
+    /// Bounces off the end of the path a few times.
+    Bounce,
+}
+
+impl Easing {
+    pub const ALL: [Easing; 6] = [
+        Easing::Linear,
+        Easing::Quad,
+        Easing::Cubic,
+        Easing::Back,
+        Easing::Elastic,
+        Easing::Bounce,
+    ];
+
+    /// The progress along the path at the point `x` of the animation. Curves
+    /// that overshoot go below 0 or above 1 in between.
+    pub fn apply(&self, x: f64) -> f64 {
+        match self {
+            Easing::Linear => x,
+            Easing::Quad => ease_in_out_quad(x),
+            Easing::Cubic => ease_in_out_cubic(x),
+            Easing::Back => ease_in_out_back(x),
+            Easing::Elastic => ease_out_elastic(x),
+            Easing::Bounce => ease_out_bounce(x),
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Easing::Linear => "linear",
+            Easing::Quad => "quad",
+            Easing::Cubic => "cubic",
+            Easing::Back => "back",
+            Easing::Elastic => "elastic",
+            Easing::Bounce => "bounce",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|easing| easing.name() == name)
+    }
+
+    /// The next easing in [Self::ALL], going back to the first after the last.
+    pub fn next(&self) -> Self {
+        let index = Self::ALL.iter().position(|easing| easing == self).unwrap();
+        Self::ALL[(index + 1) % Self::ALL.len()]
+    }
+}
+
+fn ease_in_out_quad(x: f64) -> f64 {
+    // https://easings.net/#easeInOutQuad
+    if x < 0.5 {
+        2. * x * x
+    } else {
+        1. - (-2. * x + 2.).powi(2) / 2.
+    }
+}
+
+fn ease_in_out_cubic(x: f64) -> f64 {
+    // https://easings.net/#easeInOutCubic
+    if x < 0.5 {
+        4. * x * x * x
+    } else {
+        1. - (-2. * x + 2.).powi(3) / 2.
+    }
+}
+
+fn ease_in_out_back(x: f64) -> f64 {
+    // https://easings.net/#easeInOutBack
+    const C1: f64 = 1.70158;
+    const C2: f64 = C1 * 1.525;
+
+    if x < 0.5 {
+        (2. * x).powi(2) * ((C2 + 1.) * 2. * x - C2) / 2.
+    } else {
+        ((2. * x - 2.).powi(2) * ((C2 + 1.) * (x * 2. - 2.) + C2) + 2.) / 2.
+    }
+}
+
+fn ease_out_elastic(x: f64) -> f64 {
+    // https://easings.net/#easeOutElastic
+    const C4: f64 = 2. * PI / 3.;
+
+    if x <= 0. {
+        0.
+    } else if x >= 1. {
+        1.
+    } else {
+        2f64.powf(-10. * x) * ((x * 10. - 0.75) * C4).sin() + 1.
+    }
+}
+
+fn ease_out_bounce(x: f64) -> f64 {
+    // https://easings.net/#easeOutBounce
+    const N1: f64 = 7.5625;
+    const D1: f64 = 2.75;
+
+    if x < 1. / D1 {
+        N1 * x * x
+    } else if x < 2. / D1 {
+        let x = x - 1.5 / D1;
+        N1 * x * x + 0.75
+    } else if x < 2.5 / D1 {
+        let x = x - 2.25 / D1;
+        N1 * x * x + 0.9375
+    } else {
+        let x = x - 2.625 / D1;
+        N1 * x * x + 0.984375
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_case::test_case;
+
+    const EPSILON: f64 = 1e-9;
+
+    #[test_case(Easing::Linear)]
+    #[test_case(Easing::Quad)]
+    #[test_case(Easing::Cubic)]
+    #[test_case(Easing::Back)]
+    #[test_case(Easing::Elastic)]
+    #[test_case(Easing::Bounce)]
+    fn easing_starts_at_0_and_ends_at_1(easing: Easing) {
+        assert!(easing.apply(0.).abs() < EPSILON);
+        assert!((easing.apply(1.) - 1.).abs() < EPSILON);
+    }
+
+    #[test]
+    fn every_easing_is_in_the_cycle_and_has_a_name() {
+        let mut easing = Easing::default();
+
+        for _ in Easing::ALL {
+            assert_eq!(Easing::from_name(easing.name()), Some(easing));
+            easing = easing.next();
+        }
+
+        assert_eq!(easing, Easing::default());
+    }
+
+    #[test]
+    fn quad_easing_is_symmetric() {
+        assert!((Easing::Quad.apply(0.5) - 0.5).abs() < EPSILON);
+        assert!((Easing::Quad.apply(0.25) + Easing::Quad.apply(0.75) - 1.).abs() < EPSILON);
+    }
+}
@@ -1,4 +1,6 @@
+pub mod board;
 pub mod collection;
+pub mod easing;
 pub mod path;
 pub mod piece;
 pub mod position;
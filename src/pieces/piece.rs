@@ -1,45 +1,126 @@
 use bezier_rs::SubpathTValue;
 use macroquad::{
-    color::WHITE,
-    texture::{draw_texture, Texture2D},
+    color::{self, WHITE},
+    math::Vec2,
+    shapes::{draw_circle, draw_poly, draw_rectangle},
+    texture::{draw_texture_ex, DrawTextureParams, Texture2D},
     time::get_time,
 };
 
-use crate::{Path, Position};
+use crate::{settings::Renderer, vector, Color, Easing, Path, Position, SubpathNoId};
 
 struct AnimationParams<'a> {
     path: &'a Path,
     movement_start: f64,
     movement_time: f64,
-    ghost_x: Option<f32>,
-    ghost_y: Option<f32>,
+    /// Parts of the path the movement starts and ends at, from 0 to 1.
+    path_from: f64,
+    path_to: f64,
+    easing: Easing,
+    /// How far the piece was from the start of the path when the movement
+    /// interrupted another one. It shrinks away over the movement.
+    offset: Vec2,
+}
+
+impl AnimationParams<'_> {
+    /// The part of the movement time that has passed, from 0 to 1.
+    fn time_fraction(&self) -> f64 {
+        if self.movement_time <= 0. {
+            return 1.;
+        }
+
+        ((get_time() - self.movement_start) / self.movement_time).clamp(0., 1.)
+    }
+
+    fn path_pos(&self, time_fraction: f64) -> f64 {
+        let progress = self.easing.apply(time_fraction);
+        self.path_from + (self.path_to - self.path_from) * progress
+    }
+
+    fn offset(&self, time_fraction: f64) -> Vec2 {
+        self.offset * remaining_offset(time_fraction)
+    }
+}
+
+/// The image of a wrapping piece that was leaving the board when its
+/// movement was interrupted. It finishes leaving along its old path.
+struct Trail<'a> {
+    path: &'a SubpathNoId,
+    animation: AnimationParams<'a>,
+    position: (f32, f32),
 }
 
 pub struct Piece<'a> {
     texture: &'a Texture2D,
+    color: Color,
     position: Position,
     x: f32,
     y: f32,
+    /// Width and height the piece is drawn with, which depends on the board size.
+    size: f32,
+    ghost: Option<(f32, f32)>,
     animation: Option<AnimationParams<'a>>,
+    trail: Option<Trail<'a>>,
 }
 
-fn ease_in_out_quad(x: f64) -> f64 {
-    // https://easings.net/#easeInOutQuad
-    if x < 0.5 {
-        2. * x * x
-    } else {
-        1. - (-2. * x + 2.) * (-2. * x + 2.) / 2.
+/// The point at a part of the path. Parts beyond the ends, which easings
+/// that overshoot reach, continue straight on from the end.
+fn point_along(path: &SubpathNoId, path_pos: f64) -> (f32, f32) {
+    const STEP: f64 = 0.01;
+
+    let end_pos = path_pos.clamp(0., 1.);
+    let mut point = path.evaluate(SubpathTValue::GlobalEuclidean(end_pos));
+
+    let overshoot = path_pos - end_pos;
+    if overshoot != 0. {
+        let inner_pos = end_pos - STEP.copysign(overshoot);
+        let inner = path.evaluate(SubpathTValue::GlobalEuclidean(inner_pos));
+        point += (point - inner) / STEP * overshoot.abs();
     }
+
+    (point.x as f32, point.y as f32)
+}
+
+fn offset_point((x, y): (f32, f32), offset: Vec2) -> (f32, f32) {
+    (x + offset.x, y + offset.y)
+}
+
+/// The part of the offset of an interrupting movement that is left at the
+/// time fraction, going smoothly from all of it to nothing.
+fn remaining_offset(time_fraction: f64) -> f32 {
+    let t = time_fraction.clamp(0., 1.) as f32;
+    1. - t * t * (3. - 2. * t)
+}
+
+/// The indices of the closest pair of points from the two lists.
+fn closest_pair(first: &[(f32, f32)], second: &[(f32, f32)]) -> (usize, usize) {
+    let distance = |(i, j): (usize, usize)| Vec2::from(first[i]).distance(Vec2::from(second[j]));
+
+    (0..first.len())
+        .flat_map(|i| (0..second.len()).map(move |j| (i, j)))
+        .min_by(|a, b| distance(*a).total_cmp(&distance(*b)))
+        .unwrap()
 }
 
 impl<'a> Piece<'a> {
-    pub fn new(texture: &'a Texture2D, position: Position, x: f32, y: f32) -> Self {
+    pub fn new(
+        texture: &'a Texture2D,
+        color: Color,
+        position: Position,
+        x: f32,
+        y: f32,
+        size: f32,
+    ) -> Self {
         Self {
             position,
             texture,
+            color,
             x,
             y,
+            size,
+            ghost: None,
             animation: None,
+            trail: None,
         }
     }
 
@@ -51,72 +132,225 @@ impl<'a> Piece<'a> {
         &mut self.position
     }
 
-    pub fn start_moving_along(&mut self, path: &'a Path, time: f64) {
+    pub fn start_moving_along(&mut self, path: &'a Path, time: f64, easing: Easing) {
+        self.start_moving_between(path, 0.0, 1.0, time, easing);
+    }
+
+    /// Move along a part of the path, e.g. to finish or undo a drag that
+    /// left the piece at `from`.
+    ///
+    /// A movement that interrupts another one continues from where the piece
+    /// is on the screen. The image of the piece closest to the start of the
+    /// new path is blended into it, together with the ghost, and the other
+    /// image of a wrapping piece finishes its old movement off the board.
+    pub fn start_moving_between(
+        &mut self,
+        path: &'a Path,
+        from: f64,
+        to: f64,
+        time: f64,
+        easing: Easing,
+    ) {
+        let mut offset = Vec2::ZERO;
+
+        if let Some(interrupted) = self.animation.take() {
+            let current: Vec<_> = std::iter::once((self.x, self.y))
+                .chain(self.ghost)
+                .collect();
+            let start: Vec<_> = std::iter::once(&path.main_path)
+                .chain(&path.ghost_path)
+                .map(|subpath| point_along(subpath, from))
+                .collect();
+
+            let (current_index, start_index) = closest_pair(&current, &start);
+            offset = Vec2::from(current[current_index]) - Vec2::from(start[start_index]);
+
+            // The other image of a wrapping piece is left over. There's none
+            // if the interrupted movement hasn't been updated yet.
+            let other_index = 1 - current_index;
+            let other_path = match other_index {
+                0 => Some(&interrupted.path.main_path),
+                _ => interrupted.path.ghost_path.as_ref(),
+            };
+
+            self.trail = match (current.get(other_index), other_path) {
+                (Some(position), Some(path)) => Some(Trail {
+                    path,
+                    position: *position,
+                    animation: interrupted,
+                }),
+                _ => None,
+            };
+        }
+
         self.animation = Some(AnimationParams {
             path,
             movement_start: get_time(),
             movement_time: time,
-            ghost_x: None,
-            ghost_y: None,
+            path_from: from,
+            path_to: to,
+            easing,
+            offset,
         });
     }
 
+    /// Stop any movement and hold the piece at a point of the path.
+    pub fn hold_at(&mut self, path: &Path, path_pos: f64) {
+        self.animation = None;
+        self.trail = None;
+        self.place_along(path, path_pos, Vec2::ZERO);
+    }
+
     pub fn update(&mut self) {
-        let Some(animation) = self.animation.as_mut() else {
+        self.update_trail();
+
+        let Some(animation) = self.animation.as_ref() else {
             return;
         };
 
-        let mut animation_finished = false;
+        let time_fraction = animation.time_fraction();
+        let path_pos = animation.path_pos(time_fraction);
+        let offset = animation.offset(time_fraction);
+        let path_to = animation.path_to;
+        self.place_along(animation.path, path_pos, offset);
 
-        let time_elapsed = get_time() - animation.movement_start;
-        let path_pos = if time_elapsed <= animation.movement_time {
-            ease_in_out_quad(time_elapsed / animation.movement_time)
-        } else {
-            animation_finished = true;
-            1.0
+        if time_fraction >= 1. {
+            // A wrapping piece is where its ghost is at the start of the path
+            // and where its main image is at the end
+            if path_to == 0. {
+                if let Some(ghost) = self.ghost {
+                    (self.x, self.y) = ghost;
+                }
+            }
+
+            self.animation = None;
+            self.ghost = None;
+        }
+    }
+
+    fn update_trail(&mut self) {
+        let Some(trail) = self.trail.as_mut() else {
+            return;
         };
 
-        let path_pos = SubpathTValue::GlobalEuclidean(path_pos);
+        let time_fraction = trail.animation.time_fraction();
+        if time_fraction >= 1. {
+            self.trail = None;
+            return;
+        }
 
-        let pos = animation.path.main_path.evaluate(path_pos);
+        let path_pos = trail.animation.path_pos(time_fraction);
+        let offset = trail.animation.offset(time_fraction);
+        trail.position = offset_point(point_along(trail.path, path_pos), offset);
+    }
 
-        self.x = pos.x as f32;
-        self.y = pos.y as f32;
+    fn place_along(&mut self, path: &Path, path_pos: f64, offset: Vec2) {
+        (self.x, self.y) = offset_point(point_along(&path.main_path, path_pos), offset);
 
-        if let Some(ghost_path) = animation.path.ghost_path.as_ref() {
-            let pos = ghost_path.evaluate(path_pos);
-            animation.ghost_x = Some(pos.x as f32);
-            animation.ghost_y = Some(pos.y as f32);
-        }
+        self.ghost = path
+            .ghost_path
+            .as_ref()
+            .map(|ghost_path| offset_point(point_along(ghost_path, path_pos), offset));
+    }
 
-        if animation_finished {
-            self.animation = None;
-        }
+    pub fn color(&self) -> Color {
+        self.color
     }
 
     pub fn has_same_color_as(&self, other: &Piece) -> bool {
-        self.texture == other.texture
+        self.color == other.color
     }
 
     pub fn is_animating(&self) -> bool {
-        self.animation.is_some()
+        self.animation.is_some() || self.trail.is_some()
     }
 
-    pub fn draw(&self) {
-        draw_texture(self.texture, self.x, self.y, WHITE);
+    /// Draw the piece, with the symbol of its color on top if `show_symbol`
+    /// is set.
+    pub fn draw(&self, show_symbol: bool, renderer: Renderer) {
+        let trail = self.trail.as_ref().map(|trail| trail.position);
+        let locations = std::iter::once((self.x, self.y))
+            .chain(self.ghost)
+            .chain(trail);
 
-        let Some(animation) = self.animation.as_ref() else {
-            return;
-        };
+        let scale = self.size / self.texture.width();
 
-        let Some(ghost_x) = animation.ghost_x else {
-            return;
-        };
+        for (x, y) in locations {
+            match renderer {
+                Renderer::Bitmap => draw_texture_ex(
+                    self.texture,
+                    x,
+                    y,
+                    WHITE,
+                    DrawTextureParams {
+                        dest_size: Some(Vec2::splat(self.size)),
+                        ..Default::default()
+                    },
+                ),
+                Renderer::Vector => vector::draw_piece(self.color, x, y, self.size),
+            }
 
-        let Some(ghost_y) = animation.ghost_y else {
-            return;
-        };
+            if show_symbol {
+                let half = self.size / 2.;
+                draw_symbol(self.color, x + half, y + half, scale);
+            }
+        }
+    }
+}
+
+/// Draw a shape that is different for every color, centered on the point and
+/// scaled with the piece.
+fn draw_symbol(color: Color, x: f32, y: f32, scale: f32) {
+    let radius = 8. * scale;
+    let bar_width = 5. * scale;
+    let symbol_color = color::Color::from_rgba(0x21, 0x21, 0x21, 0xff);
+
+    match color {
+        Color::Yellow => draw_circle(x, y, radius, symbol_color),
+        Color::Blue => draw_poly(x, y, 4, radius * 1.2, 45., symbol_color),
+        Color::Red => draw_poly(x, y + radius * 0.2, 3, radius * 1.2, -90., symbol_color),
+        Color::Purple => draw_poly(x, y, 4, radius * 1.2, 0., symbol_color),
+        Color::Green => {
+            draw_rectangle(
+                x - radius,
+                y - bar_width / 2.,
+                2. * radius,
+                bar_width,
+                symbol_color,
+            );
+            draw_rectangle(
+                x - bar_width / 2.,
+                y - radius,
+                bar_width,
+                2. * radius,
+                symbol_color,
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_case::test_case;
+
+    #[test_case(0., 1.)]
+    #[test_case(0.5, 0.5)]
+    #[test_case(1., 0.)]
+    #[test_case(1.5, 0.; "past the end")]
+    fn offset_shrinks_to_nothing(time_fraction: f64, expected: f32) {
+        assert!((remaining_offset(time_fraction) - expected).abs() < 1e-6);
+    }
+
+    #[test]
+    fn closest_images_are_paired() {
+        // A wrapping piece halfway off the board and the starts of a path
+        // with a ghost on the other side
+        let current = [(-20., 100.), (430., 100.)];
+        let start = [(50., 100.), (-400., 100.)];
 
-        draw_texture(self.texture, ghost_x, ghost_y, WHITE);
+        assert_eq!(closest_pair(&current, &start), (0, 0));
+        assert_eq!(closest_pair(&current[1..], &start), (0, 0));
+        assert_eq!(closest_pair(&[(-380., 90.)], &start), (0, 1));
     }
 }
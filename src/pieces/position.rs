@@ -1,12 +1,10 @@
-use std::{error::Error, fmt::Display};
-
-const DIMENSION: u8 = 9;
-const SQUARE_SIZE: u8 = DIMENSION / 3;
+use std::{error::Error, fmt::Display, ops::Range};
 
 #[derive(Debug)]
 #[non_exhaustive]
 pub enum PieceError {
     InvalidRowCol,
+    InvalidSize,
     CantRotate,
     CantSlide,
 }
@@ -15,6 +13,7 @@ impl Display for PieceError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             PieceError::InvalidRowCol => write!(f, "Invalid combination of row and column"),
+            PieceError::InvalidSize => write!(f, "Invalid size of the squares"),
             PieceError::CantRotate => write!(f, "Trying to rotate a piece in the center square"),
             PieceError::CantSlide => write!(f, "Trying to slide a piece in invalid direction"),
         }
@@ -23,10 +22,77 @@ impl Display for PieceError {
 
 impl Error for PieceError {}
 
+/// Size of the board, given by the number of pieces along the side of each of
+/// its five squares. The rows and columns of the board are numbered across
+/// the whole cross, i.e. from 0 to three times the square size.
+#[derive(PartialEq, Eq, Hash, Copy, Clone, Debug)]
+pub struct BoardSize {
+    square_size: u8,
+}
+
+impl Default for BoardSize {
+    fn default() -> Self {
+        Self::STANDARD
+    }
+}
+
+impl BoardSize {
+    /// The board with 3x3 squares that the game was made for.
+    pub const STANDARD: BoardSize = BoardSize { square_size: 3 };
+
+    pub const ALL: [BoardSize; 4] = [
+        BoardSize { square_size: 2 },
+        BoardSize { square_size: 3 },
+        BoardSize { square_size: 4 },
+        BoardSize { square_size: 5 },
+    ];
+
+    pub fn new(square_size: u8) -> Result<Self, PieceError> {
+        Self::ALL
+            .into_iter()
+            .find(|size| size.square_size == square_size)
+            .ok_or(PieceError::InvalidSize)
+    }
+
+    /// Number of pieces along the side of a square.
+    pub fn square_size(&self) -> u8 {
+        self.square_size
+    }
+
+    /// Number of rows and columns across the whole board.
+    pub fn dimension(&self) -> u8 {
+        3 * self.square_size
+    }
+
+    /// Number of valid positions on the board, i.e. the number of pieces.
+    pub fn position_count(&self) -> usize {
+        let square_size = self.square_size as usize;
+        Square::ALL.len() * square_size * square_size
+    }
+
+    /// The rows and columns that go through the center square, which are the
+    /// ones that can slide.
+    pub fn middle_lines(&self) -> Range<u8> {
+        self.square_size..2 * self.square_size
+    }
+
+    /// The rings that can rotate, from the innermost outward.
+    pub fn rings(&self) -> Range<u8> {
+        0..self.square_size
+    }
+
+    /// The next larger size in [Self::ALL], going back to the smallest after the largest.
+    pub fn next(&self) -> Self {
+        let index = Self::ALL.iter().position(|size| size == self).unwrap();
+        Self::ALL[(index + 1) % Self::ALL.len()]
+    }
+}
+
 #[derive(PartialEq, Eq, Hash, Copy, Clone, Debug)]
 pub struct Position {
     row: u8,
     col: u8,
+    size: BoardSize,
 }
 
 #[derive(Copy, Clone)]
@@ -39,7 +105,18 @@ pub enum Manipulation {
     SlideDown,
 }
 
-#[derive(PartialEq, Eq, Debug)]
+impl Manipulation {
+    pub const ALL: [Manipulation; 6] = [
+        Manipulation::RotateClockwise,
+        Manipulation::RotateAnticlockwise,
+        Manipulation::SlideLeft,
+        Manipulation::SlideRight,
+        Manipulation::SlideUp,
+        Manipulation::SlideDown,
+    ];
+}
+
+#[derive(PartialEq, Eq, Hash, Copy, Clone, Debug)]
 pub enum Square {
     North,
     South,
@@ -48,6 +125,16 @@ pub enum Square {
     East,
 }
 
+impl Square {
+    pub const ALL: [Square; 5] = [
+        Square::North,
+        Square::West,
+        Square::Center,
+        Square::East,
+        Square::South,
+    ];
+}
+
 enum RotationDirection {
     Clockwise,
     Anticlockwise,
@@ -61,16 +148,43 @@ enum SlideDirection {
 }
 
 impl Position {
-    pub fn new(row: u8, col: u8) -> Result<Self, PieceError> {
-        let ret = Self { row, col };
+    pub fn new(size: BoardSize, row: u8, col: u8) -> Result<Self, PieceError> {
+        let ret = Self { row, col, size };
 
-        if ret.is_row_in_middle() || ret.is_col_in_middle() {
+        if row >= size.dimension() || col >= size.dimension() {
+            Err(PieceError::InvalidRowCol)
+        } else if ret.is_row_in_middle() || ret.is_col_in_middle() {
             Ok(ret)
         } else {
             Err(PieceError::InvalidRowCol)
         }
     }
 
+    /// Iterate over all the valid positions of a board in row-major order.
+    pub fn all(size: BoardSize) -> impl Iterator<Item = Position> {
+        let dimension = size.dimension();
+
+        (0..dimension)
+            .flat_map(move |row| (0..dimension).map(move |col| Position { row, col, size }))
+            .filter(|position| position.is_row_in_middle() || position.is_col_in_middle())
+    }
+
+    /// Index of this position in the row-major order used by [Position::all].
+    pub fn index(&self) -> usize {
+        let size = self.size.square_size() as usize;
+        let square_cells = size * size;
+        let row = self.row as usize;
+        let col = self.col as usize;
+
+        if row < size {
+            row * size + col - size
+        } else if row < 2 * size {
+            square_cells + (row - size) * 3 * size + col
+        } else {
+            4 * square_cells + (row - 2 * size) * size + col - size
+        }
+    }
+
     pub fn apply_manipulation(&mut self, manipulation: Manipulation) -> Result<(), PieceError> {
         use Manipulation::*;
         use RotationDirection::*;
@@ -94,9 +208,14 @@ impl Position {
         self.col
     }
 
+    pub fn size(&self) -> BoardSize {
+        self.size
+    }
+
     pub fn square(&self) -> Square {
-        let square_row = self.row / SQUARE_SIZE;
-        let square_col = self.col / SQUARE_SIZE;
+        let square_size = self.size.square_size();
+        let square_row = self.row / square_size;
+        let square_col = self.col / square_size;
 
         match (square_row, square_col) {
             (0, 1) => Square::North,
@@ -123,12 +242,12 @@ impl Position {
             self.col
         };
 
-        let center = DIMENSION / 2;
+        let square_size = self.size.square_size();
 
-        if distance_coordinate < center {
-            Some(SQUARE_SIZE - distance_coordinate - 1)
+        if distance_coordinate < square_size {
+            Some(square_size - distance_coordinate - 1)
         } else {
-            Some(distance_coordinate - 2 * SQUARE_SIZE)
+            Some(distance_coordinate - 2 * square_size)
         }
     }
 
@@ -137,8 +256,9 @@ impl Position {
             return Err(PieceError::CantRotate);
         }
 
-        let flip_row_and_swap = |row: u8, col: u8| (col, DIMENSION - 1 - row);
-        let flip_col_and_swap = |row: u8, col: u8| (DIMENSION - 1 - col, row);
+        let last = self.size.dimension() - 1;
+        let flip_row_and_swap = |row: u8, col: u8| (col, last - row);
+        let flip_col_and_swap = |row: u8, col: u8| (last - col, row);
 
         let (new_row, new_col) = match direction {
             RotationDirection::Clockwise => flip_row_and_swap(self.row, self.col),
@@ -160,22 +280,25 @@ impl Position {
             _ => (),
         }
 
+        let square_size = self.size.square_size();
+        let dimension = self.size.dimension();
+
         match direction {
-            Left => self.col = (DIMENSION + self.col - SQUARE_SIZE) % DIMENSION,
-            Right => self.col = (self.col + SQUARE_SIZE) % DIMENSION,
-            Up => self.row = (DIMENSION + self.row - SQUARE_SIZE) % DIMENSION,
-            Down => self.row = (self.row + SQUARE_SIZE) % DIMENSION,
+            Left => self.col = (dimension + self.col - square_size) % dimension,
+            Right => self.col = (self.col + square_size) % dimension,
+            Up => self.row = (dimension + self.row - square_size) % dimension,
+            Down => self.row = (self.row + square_size) % dimension,
         }
 
         Ok(())
     }
 
     fn is_row_in_middle(&self) -> bool {
-        self.row >= SQUARE_SIZE && self.row < 2 * SQUARE_SIZE
+        self.size.middle_lines().contains(&self.row)
     }
 
     fn is_col_in_middle(&self) -> bool {
-        self.col >= SQUARE_SIZE && self.col < 2 * SQUARE_SIZE
+        self.size.middle_lines().contains(&self.col)
     }
 }
 
@@ -199,14 +322,14 @@ mod tests {
     #[test_case(3, 3, true; "center square")]
 
     fn new_returns_expected_value(row: u8, col: u8, expected: bool) {
-        let position = Position::new(row, col);
+        let position = Position::new(BoardSize::STANDARD, row, col);
 
         assert_eq!(position.is_ok(), expected);
     }
 
     #[test]
     fn rotate_fails_for_center_square() {
-        let position = Position::new(4, 4);
+        let position = Position::new(BoardSize::STANDARD, 4, 4);
         let ret = position.unwrap().rotate(RotationDirection::Clockwise);
 
         assert!(matches!(ret.err().unwrap(), PieceError::CantRotate));
@@ -243,7 +366,7 @@ mod tests {
         expected_row: u8,
         expected_col: u8,
     ) {
-        let mut position = Position::new(row, col).unwrap();
+        let mut position = Position::new(BoardSize::STANDARD, row, col).unwrap();
         position.rotate(direction).unwrap();
 
         assert_eq!(position.row, expected_row);
@@ -259,7 +382,7 @@ mod tests {
     #[test_case(6, 3, SlideDirection::Left; "southern square, left")]
     #[test_case(7, 4, SlideDirection::Right; "southern square, right")]
     fn sliding_in_invalid_direction_fails(row: u8, col: u8, direction: SlideDirection) {
-        let mut position = Position::new(row, col).unwrap();
+        let mut position = Position::new(BoardSize::STANDARD, row, col).unwrap();
 
         let result = position.slide(direction);
 
@@ -281,7 +404,7 @@ mod tests {
         expected_row: u8,
         expected_col: u8,
     ) {
-        let mut position = Position::new(row, col).unwrap();
+        let mut position = Position::new(BoardSize::STANDARD, row, col).unwrap();
 
         position.slide(direction).unwrap();
 
@@ -304,18 +427,68 @@ mod tests {
     #[test_case(4, 7, Some(1))]
     #[test_case(4, 8, Some(2))]
     fn ring_returns_the_expected_value(row: u8, col: u8, expected_ring: Option<u8>) {
-        let position = Position::new(row, col).unwrap();
+        let position = Position::new(BoardSize::STANDARD, row, col).unwrap();
 
         assert_eq!(position.ring(), expected_ring);
     }
 
+    #[test_case(2)]
+    #[test_case(3)]
+    #[test_case(4)]
+    #[test_case(5)]
+    fn index_matches_the_order_of_all(square_size: u8) {
+        let size = BoardSize::new(square_size).unwrap();
+
+        for (expected, position) in Position::all(size).enumerate() {
+            assert_eq!(position.index(), expected);
+        }
+
+        assert_eq!(Position::all(size).count(), size.position_count());
+    }
+
+    #[test_case(2, 0, 2, 2, 5; "smallest board")]
+    #[test_case(4, 1, 6, 6, 10; "larger board")]
+    #[test_case(5, 4, 5, 5, 10; "largest board")]
+    fn rotate_works_on_every_board_size(
+        square_size: u8,
+        row: u8,
+        col: u8,
+        expected_row: u8,
+        expected_col: u8,
+    ) {
+        let size = BoardSize::new(square_size).unwrap();
+        let mut position = Position::new(size, row, col).unwrap();
+
+        position.rotate(RotationDirection::Clockwise).unwrap();
+
+        assert_eq!((position.row, position.col), (expected_row, expected_col));
+    }
+
+    #[test_case(2, 1; "smallest board")]
+    #[test_case(4, 3; "larger board")]
+    #[test_case(5, 4; "largest board")]
+    fn outer_ring_is_the_last_one(square_size: u8, expected_ring: u8) {
+        let size = BoardSize::new(square_size).unwrap();
+        let position = Position::new(size, 0, size.square_size()).unwrap();
+
+        assert_eq!(position.ring(), Some(expected_ring));
+        assert_eq!(size.rings().last(), Some(expected_ring));
+    }
+
+    #[test]
+    fn only_supported_sizes_can_be_made() {
+        assert!(matches!(BoardSize::new(1), Err(PieceError::InvalidSize)));
+        assert!(matches!(BoardSize::new(6), Err(PieceError::InvalidSize)));
+        assert_eq!(BoardSize::new(3).unwrap(), BoardSize::STANDARD);
+    }
+
     #[test_case(1, 4, Square::North; "north")]
     #[test_case(7, 5, Square::South; "south")]
     #[test_case(4, 2, Square::West; "west")]
     #[test_case(3, 7, Square::East; "east")]
     #[test_case(5, 5, Square::Center; "center")]
     fn square_returns_the_expected_value(row: u8, col: u8, expected_square: Square) {
-        let position = Position::new(row, col).unwrap();
+        let position = Position::new(BoardSize::STANDARD, row, col).unwrap();
 
         assert_eq!(position.square(), expected_square);
     }
@@ -0,0 +1,183 @@
+//! Pointer input from the mouse and from touches.
+//!
+//! Touches are turned into the same [MouseEvent]s as the mouse, so buttons
+//! and drags work the same way on phones. Every touch gets its own events, as
+//! if it were a mouse of its own, so fingers don't interfere with each other.
+//! Touches have no hover, so the pointer of a touch exits once it ends. All
+//! the coordinates are on the board, see [crate::viewport].
+
+use macroquad::input::{
+    is_mouse_button_pressed, is_mouse_button_released, mouse_delta_position, mouse_position,
+    touches, MouseButton, Touch, TouchPhase,
+};
+use macroquad::math::Vec2;
+
+use crate::{button::MouseEvent, viewport::Viewport};
+
+/// The mouse or one of the touches.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PointerId {
+    Mouse,
+    Touch(u64),
+}
+
+/// Events since the last frame, from the mouse and every touch, grouped by
+/// the pointer they come from.
+pub fn events() -> Vec<(PointerId, Vec<MouseEvent>)> {
+    let viewport = Viewport::current();
+
+    let mut events = vec![(PointerId::Mouse, mouse_events())];
+    events.extend(touch_events(&touches()));
+    events
+        .into_iter()
+        .filter(|(_, events)| !events.is_empty())
+        .map(|(id, events)| {
+            let events = events
+                .into_iter()
+                .map(|event| to_board(event, &viewport))
+                .collect();
+            (id, events)
+        })
+        .collect()
+}
+
+fn touch_events(touches: &[Touch]) -> Vec<(PointerId, Vec<MouseEvent>)> {
+    use MouseEvent::*;
+
+    let mut events: Vec<(PointerId, Vec<MouseEvent>)> = vec![];
+
+    for touch in touches {
+        let (x, y) = touch.position.into();
+
+        let new_events = match touch.phase {
+            TouchPhase::Started => vec![Moved { x, y }, Pressed { x, y }],
+            TouchPhase::Moved => vec![Moved { x, y }],
+            TouchPhase::Ended | TouchPhase::Cancelled => vec![Moved { x, y }, Released, Exited],
+            TouchPhase::Stationary => vec![],
+        };
+
+        let id = PointerId::Touch(touch.id);
+        match events.iter_mut().find(|(other, _)| *other == id) {
+            Some((_, touch_events)) => touch_events.extend(new_events),
+            None => events.push((id, new_events)),
+        }
+    }
+
+    events
+}
+
+fn to_board(event: MouseEvent, viewport: &Viewport) -> MouseEvent {
+    use MouseEvent::*;
+
+    match event {
+        Moved { x, y } => {
+            let (x, y) = viewport.to_board(x, y);
+            Moved { x, y }
+        }
+        Pressed { x, y } => {
+            let (x, y) = viewport.to_board(x, y);
+            Pressed { x, y }
+        }
+        event => event,
+    }
+}
+
+fn mouse_events() -> Vec<MouseEvent> {
+    let (x, y) = mouse_position();
+    let mut events = vec![];
+
+    if mouse_delta_position() != Vec2::ZERO {
+        events.push(MouseEvent::Moved { x, y });
+    }
+
+    if is_mouse_button_pressed(MouseButton::Left) {
+        events.push(MouseEvent::Pressed { x, y });
+    }
+
+    if is_mouse_button_released(MouseButton::Left) {
+        events.push(MouseEvent::Released);
+    }
+
+    events
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use MouseEvent::*;
+
+    fn touch(id: u64, phase: TouchPhase, x: f32, y: f32) -> Touch {
+        Touch {
+            id,
+            phase,
+            position: Vec2::new(x, y),
+        }
+    }
+
+    #[test]
+    fn tap_presses_releases_and_exits() {
+        let start = touch_events(&[touch(1, TouchPhase::Started, 10., 20.)]);
+        let end = touch_events(&[touch(1, TouchPhase::Ended, 12., 20.)]);
+
+        assert_eq!(
+            start,
+            vec![(
+                PointerId::Touch(1),
+                vec![Moved { x: 10., y: 20. }, Pressed { x: 10., y: 20. }]
+            )]
+        );
+        assert_eq!(
+            end,
+            vec![(
+                PointerId::Touch(1),
+                vec![Moved { x: 12., y: 20. }, Released, Exited]
+            )]
+        );
+    }
+
+    #[test]
+    fn events_are_mapped_to_the_board() {
+        let viewport = Viewport::fit(1000., 500.);
+
+        assert_eq!(
+            to_board(Pressed { x: 300., y: 20. }, &viewport),
+            Pressed { x: 50., y: 20. }
+        );
+        assert_eq!(to_board(Released, &viewport), Released);
+    }
+
+    #[test]
+    fn stationary_touch_makes_no_events() {
+        let events = touch_events(&[touch(1, TouchPhase::Stationary, 10., 20.)]);
+
+        assert_eq!(events, vec![(PointerId::Touch(1), vec![])]);
+    }
+
+    #[test]
+    fn every_touch_has_its_own_events() {
+        let events = touch_events(&[
+            touch(1, TouchPhase::Moved, 15., 20.),
+            touch(2, TouchPhase::Started, 100., 100.),
+            touch(1, TouchPhase::Ended, 16., 20.),
+        ]);
+
+        assert_eq!(
+            events,
+            vec![
+                (
+                    PointerId::Touch(1),
+                    vec![
+                        Moved { x: 15., y: 20. },
+                        Moved { x: 16., y: 20. },
+                        Released,
+                        Exited
+                    ]
+                ),
+                (
+                    PointerId::Touch(2),
+                    vec![Moved { x: 100., y: 100. }, Pressed { x: 100., y: 100. }]
+                ),
+            ]
+        );
+    }
+}
@@ -0,0 +1,290 @@
+//! Variants of the puzzle played on the same board.
+//!
+//! A ruleset decides which manipulations can be made and when the board is
+//! solved. The rulesets are stored by name: `standard`, `rotations`, `slides`,
+//! `checkerboard` and `target:<board>`, where the board is the canonical
+//! string of a [BoardState] that has to be reached from the solved one.
+
+use crate::{pieces::position::Square, scramble, BoardSize, BoardState, Color, Manipulation};
+
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum Ruleset {
+    /// Every manipulation, and every square has to be a single color.
+    #[default]
+    Standard,
+    /// Same as [Ruleset::Standard], but only the rings can be rotated.
+    RotationsOnly,
+    /// Same as [Ruleset::Standard], but only the rows and columns can be slid.
+    SlidesOnly,
+    /// Every manipulation, and the colors of every square have to alternate
+    /// like on a checkerboard, see [checkerboard].
+    Checkerboard,
+    /// Every manipulation, and every square has to have the colors of the same
+    /// square of the board, which also sets the board size.
+    Target(BoardState),
+}
+
+impl Ruleset {
+    /// Rulesets that [Ruleset::next] goes through.
+    const PRESETS: [Ruleset; 4] = [
+        Ruleset::Standard,
+        Ruleset::RotationsOnly,
+        Ruleset::SlidesOnly,
+        Ruleset::Checkerboard,
+    ];
+
+    pub fn name(&self) -> String {
+        match self {
+            Ruleset::Standard => "standard".into(),
+            Ruleset::RotationsOnly => "rotations".into(),
+            Ruleset::SlidesOnly => "slides".into(),
+            Ruleset::Checkerboard => "checkerboard".into(),
+            Ruleset::Target(target) => format!("target:{}", target),
+        }
+    }
+
+    /// The ruleset with the name. Targets that can't be reached from the
+    /// solved board are refused.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "standard" => Some(Ruleset::Standard),
+            "rotations" => Some(Ruleset::RotationsOnly),
+            "slides" => Some(Ruleset::SlidesOnly),
+            "checkerboard" => Some(Ruleset::Checkerboard),
+            _ => name
+                .strip_prefix("target:")?
+                .parse()
+                .ok()
+                .filter(scramble::is_reachable)
+                .map(Ruleset::Target),
+        }
+    }
+
+    /// The next preset, then the configured target board if there is one,
+    /// going back to the standard rules after it.
+    pub fn next(&self, target: Option<&BoardState>) -> Self {
+        let mut cycle = Self::PRESETS.to_vec();
+        cycle.extend(target.cloned().map(Ruleset::Target));
+
+        let index = cycle.iter().position(|ruleset| ruleset == self);
+        let next = index.map_or(0, |index| (index + 1) % cycle.len());
+        cycle.swap_remove(next)
+    }
+
+    /// The board size the ruleset is made for, if it only works on one.
+    pub fn board_size(&self) -> Option<BoardSize> {
+        match self {
+            Ruleset::Target(target) => Some(target.size()),
+            _ => None,
+        }
+    }
+
+    pub fn allows(&self, manipulation: Manipulation) -> bool {
+        use Manipulation::*;
+
+        let is_rotation = matches!(manipulation, RotateClockwise(_) | RotateAnticlockwise(_));
+
+        match self {
+            Ruleset::RotationsOnly => is_rotation,
+            Ruleset::SlidesOnly => !is_rotation,
+            Ruleset::Standard | Ruleset::Checkerboard | Ruleset::Target(_) => true,
+        }
+    }
+
+    /// The manipulations of a board of the size that are allowed, in the
+    /// order of [Manipulation::all]. A manipulation is allowed together with
+    /// its inverse, so they stay next to each other.
+    pub fn manipulations(&self, size: BoardSize) -> Vec<Manipulation> {
+        Manipulation::all(size)
+            .into_iter()
+            .filter(|manipulation| self.allows(*manipulation))
+            .collect()
+    }
+
+    /// Whether every manipulation of the board is allowed.
+    pub fn allows_all(&self) -> bool {
+        !matches!(self, Ruleset::RotationsOnly | Ruleset::SlidesOnly)
+    }
+
+    /// Whether the solver can give hints. It only knows the standard rules.
+    pub fn has_hints(&self) -> bool {
+        *self == Ruleset::Standard
+    }
+
+    /// The exact board that has to be reached, if there is one.
+    pub fn target(&self, size: BoardSize) -> Option<BoardState> {
+        match self {
+            Ruleset::Standard | Ruleset::RotationsOnly | Ruleset::SlidesOnly => None,
+            Ruleset::Checkerboard => Some(checkerboard(size)),
+            Ruleset::Target(target) => Some(target.clone()),
+        }
+    }
+
+    pub fn is_solved(&self, state: &BoardState) -> bool {
+        is_solved_for(state, self.target(state.size()).as_ref())
+    }
+
+    pub fn is_square_solved(&self, state: &BoardState, square: Square) -> bool {
+        is_square_solved_for(state, self.target(state.size()).as_ref(), square)
+    }
+}
+
+/// Same as [Ruleset::is_solved] for a ruleset with the target, for callers
+/// that keep the target instead of making it for every check.
+pub fn is_solved_for(state: &BoardState, target: Option<&BoardState>) -> bool {
+    Square::ALL
+        .iter()
+        .all(|square| is_square_solved_for(state, target, *square))
+}
+
+/// Same as [Ruleset::is_square_solved] for a ruleset with the target.
+pub fn is_square_solved_for(
+    state: &BoardState,
+    target: Option<&BoardState>,
+    square: Square,
+) -> bool {
+    let Some(target) = target else {
+        return state.is_square_solved(square);
+    };
+
+    state
+        .iter()
+        .filter(|(position, _)| position.square() == square)
+        .all(|(position, color)| target.color_at(&position) == color)
+}
+
+/// The solved board with every other piece of a square swapped for the color
+/// of the next square in [Square::ALL]. The pattern starts at the top left
+/// corner of every square, so that all the colors keep their number of pieces.
+pub fn checkerboard(size: BoardSize) -> BoardState {
+    let solved = BoardState::new(size);
+    let square_color = |square: Square| {
+        solved
+            .iter()
+            .find(|(position, _)| position.square() == square)
+            .map(|(_, color)| color)
+            .unwrap()
+    };
+
+    let colors: Vec<Color> = solved
+        .iter()
+        .map(|(position, color)| {
+            let s = size.square_size();
+            let is_swapped = (position.row() % s + position.col() % s) % 2 == 1;
+
+            if !is_swapped {
+                return color;
+            }
+
+            let index = Square::ALL
+                .iter()
+                .position(|square| *square == position.square())
+                .unwrap();
+            square_color(Square::ALL[(index + 1) % Square::ALL.len()])
+        })
+        .collect();
+
+    BoardState::from_colors(&colors).unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{scramble::generate_shuffle_manipulations, Position};
+    use test_case::test_case;
+
+    #[test]
+    fn every_preset_is_in_the_cycle_and_has_a_name() {
+        let mut ruleset = Ruleset::default();
+
+        for _ in Ruleset::PRESETS {
+            assert_eq!(Ruleset::from_name(&ruleset.name()), Some(ruleset.clone()));
+            ruleset = ruleset.next(None);
+        }
+
+        assert_eq!(ruleset, Ruleset::default());
+    }
+
+    #[test]
+    fn target_round_trips_and_stays_in_the_cycle() {
+        let board = checkerboard(BoardSize::STANDARD);
+        let target = Ruleset::Target(board.clone());
+
+        assert_eq!(Ruleset::from_name(&target.name()), Some(target.clone()));
+        assert_eq!(target.board_size(), Some(BoardSize::STANDARD));
+
+        assert_eq!(Ruleset::Checkerboard.next(Some(&board)), target);
+        assert_eq!(target.next(Some(&board)), Ruleset::Standard);
+        assert_eq!(target.next(None), Ruleset::Standard);
+    }
+
+    #[test]
+    fn unreachable_target_is_refused() {
+        // Corners and edges of the squares never trade places
+        let size = BoardSize::STANDARD;
+        let corner = Position::new(size, 0, 3).unwrap().index();
+        let edge = Position::new(size, 3, 4).unwrap().index();
+        let mut colors = BoardState::new(size).colors().to_vec();
+        colors.swap(corner, edge);
+        let unreachable = BoardState::from_colors(&colors).unwrap();
+
+        assert_eq!(Ruleset::from_name(&format!("target:{}", unreachable)), None);
+        assert_eq!(Ruleset::from_name("target:GGG"), None);
+    }
+
+    #[test_case(Ruleset::RotationsOnly, 6)]
+    #[test_case(Ruleset::SlidesOnly, 12)]
+    #[test_case(Ruleset::Checkerboard, 18)]
+    fn ruleset_limits_the_manipulations(ruleset: Ruleset, expected: usize) {
+        let manipulations = ruleset.manipulations(BoardSize::STANDARD);
+
+        assert_eq!(manipulations.len(), expected);
+        assert_eq!(ruleset.allows_all(), expected == 18);
+
+        // Inverses stay next to each other, which scrambles rely on
+        for pair in manipulations.chunks(2) {
+            assert_eq!(pair[0].inverse(), pair[1]);
+        }
+    }
+
+    #[test_case(2)]
+    #[test_case(3)]
+    #[test_case(4)]
+    #[test_case(5)]
+    fn checkerboard_can_be_reached_on_every_board_size(square_size: u8) {
+        let size = BoardSize::new(square_size).unwrap();
+
+        assert!(scramble::is_reachable(&checkerboard(size)));
+    }
+
+    #[test_case(0, 3, Color::Green; "north corner")]
+    #[test_case(0, 4, Color::Purple; "north edge")]
+    #[test_case(4, 4, Color::Yellow; "center of the center")]
+    #[test_case(3, 4, Color::Blue; "center edge")]
+    #[test_case(7, 3, Color::Green; "south edge")]
+    fn checkerboard_alternates_the_colors_of_the_squares(row: u8, col: u8, expected: Color) {
+        let size = BoardSize::STANDARD;
+        let position = Position::new(size, row, col).unwrap();
+
+        assert_eq!(checkerboard(size).color_at(&position), expected);
+    }
+
+    #[test]
+    fn target_rules_need_the_exact_board() {
+        let checkerboard_rules = Ruleset::Checkerboard;
+        let solved = BoardState::default();
+
+        assert!(Ruleset::Standard.is_solved(&solved));
+        assert!(!checkerboard_rules.is_solved(&solved));
+        assert!(checkerboard_rules.is_solved(&checkerboard(BoardSize::STANDARD)));
+        assert!(!checkerboard_rules.is_square_solved(&solved, Square::Center));
+    }
+
+    #[test]
+    fn shuffle_only_uses_allowed_manipulations() {
+        let ruleset = Ruleset::RotationsOnly;
+        let manipulations = generate_shuffle_manipulations(1234, 50, BoardSize::STANDARD, &ruleset);
+
+        assert!(manipulations.iter().all(|m| ruleset.allows(*m)));
+    }
+}
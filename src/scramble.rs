@@ -0,0 +1,725 @@
+use std::collections::{hash_map::Entry, HashMap, VecDeque};
+
+use macroquad::{miniquad::date::now, rand::RandGenerator};
+
+use crate::{ruleset::Ruleset, BoardSize, BoardState, Color, Manipulation, Position};
+
+/// Highest number of manipulations tried when moving a single piece into place
+/// before falling back to a three piece cycle.
+const MAX_PLACEMENT_DEPTH: usize = 3;
+
+type Cells = Vec<Color>;
+
+/// For every cell, the cell that its piece is moved to.
+type Destinations = Vec<usize>;
+
+/// A seed that is different every time the game is started.
+pub fn random_seed() -> u64 {
+    now() as u64
+}
+
+/// Generate `count` random manipulations of a board of the size that the
+/// ruleset allows. The same seed always produces the same manipulations, so a
+/// scramble can be shared by sharing its seed.
+pub fn generate_shuffle_manipulations(
+    seed: u64,
+    count: usize,
+    size: BoardSize,
+    ruleset: &Ruleset,
+) -> Vec<Manipulation> {
+    shuffle(&ruleset.manipulations(size), seed, count)
+}
+
+/// `count` random manipulations picked from `all_manipulations`, where every
+/// manipulation is next to its inverse.
+fn shuffle(all_manipulations: &[Manipulation], seed: u64, count: usize) -> Vec<Manipulation> {
+    if all_manipulations.is_empty() {
+        return vec![];
+    }
+
+    let rng = RandGenerator::new();
+    rng.srand(seed);
+
+    let mut manipulations = vec![];
+    let mut last_index: Option<usize> = None;
+
+    // With only one manipulation and its inverse there is nothing else to pick
+    let can_avoid_last = all_manipulations.len() > 2;
+
+    for _ in 0..count {
+        let mut index = rng.rand() as usize % all_manipulations.len();
+
+        // Do not allow the same manipulation to happen twice or the two opposite
+        // manipulations to happen in one after the other
+        while can_avoid_last && last_index.is_some_and(|last| index / 2 == last / 2) {
+            index = rng.rand() as usize % all_manipulations.len();
+        }
+
+        manipulations.push(all_manipulations[index]);
+        last_index = Some(index);
+    }
+
+    manipulations
+}
+
+/// Generate manipulations that lead from the solved board to a board sampled
+/// uniformly from all the reachable color arrangements. The same seed always
+/// produces the same manipulations.
+pub fn generate_random_state_manipulations(seed: u64, size: BoardSize) -> Vec<Manipulation> {
+    let mut builder = random_state_builder(seed, size);
+    while !builder.step() {}
+
+    debug_assert_eq!(builder.cells, builder.target);
+
+    builder.manipulations()
+}
+
+/// Start building the manipulations of [generate_random_state_manipulations].
+/// Building them takes a few hundred milliseconds on the largest board, so the
+/// game spreads the steps over several frames.
+pub fn random_state_builder(seed: u64, size: BoardSize) -> SequenceBuilder {
+    let moves = Moves::new(size);
+    let target = random_reachable_state(&moves, seed);
+
+    SequenceBuilder::with_moves(moves, &BoardState::new(size), &target)
+}
+
+/// Whether the board can be reached from the solved board. Manipulations only
+/// move pieces within groups of cells, like the corners, edges and centers of
+/// the squares of the standard board, so every one of those groups has to keep
+/// the colors of the solved board.
+pub fn is_reachable(state: &BoardState) -> bool {
+    let moves = Moves::new(state.size());
+    let solved = cells_of(&BoardState::new(state.size()));
+    let cells = cells_of(state);
+
+    moves.orbits.iter().all(|orbit| {
+        let mut expected: Vec<usize> = orbit.iter().map(|c| color_index(solved[*c])).collect();
+        let mut actual: Vec<usize> = orbit.iter().map(|c| color_index(cells[*c])).collect();
+        expected.sort();
+        actual.sort();
+        expected == actual
+    })
+}
+
+fn random_reachable_state(moves: &Moves, seed: u64) -> BoardState {
+    let rng = RandGenerator::new();
+    rng.srand(seed);
+
+    let mut cells = cells_of(&BoardState::new(moves.size));
+
+    // Shuffling the colors within every orbit gives every arrangement of the
+    // colors the same probability. Orbits that allow only even permutations
+    // contain repeated colors, so every arrangement of them is still reachable.
+    for orbit in &moves.orbits {
+        let mut colors: Vec<Color> = orbit.iter().map(|cell| cells[*cell]).collect();
+
+        for i in (1..colors.len()).rev() {
+            let j = rng.gen_range(0, i + 1);
+            colors.swap(i, j);
+        }
+
+        for (cell, color) in orbit.iter().zip(colors) {
+            cells[*cell] = color;
+        }
+    }
+
+    BoardState::from_colors(&cells).unwrap()
+}
+
+struct Moves {
+    size: BoardSize,
+    manipulations: Vec<Manipulation>,
+    destinations: Vec<Destinations>,
+    /// Groups of cells between which the pieces can move.
+    orbits: Vec<Vec<usize>>,
+    /// Short manipulation sequences that cycle exactly three cells, as `(p, q, r)`
+    /// where the piece at `p` goes to `q`, `q` to `r` and `r` to `p`.
+    cycles: Vec<([usize; 3], Vec<usize>)>,
+}
+
+impl Moves {
+    fn new(size: BoardSize) -> Self {
+        let manipulations = Manipulation::all(size);
+        let destinations: Vec<Destinations> = manipulations
+            .iter()
+            .map(|m| destinations_of(*m, size))
+            .collect();
+
+        let orbits = orbits_of(&destinations);
+        let cycles = three_cycles(&destinations, &manipulations);
+
+        Self {
+            size,
+            manipulations,
+            destinations,
+            orbits,
+            cycles,
+        }
+    }
+
+    fn orbit_of(&self, cell: usize) -> &[usize] {
+        self.orbits
+            .iter()
+            .find(|orbit| orbit.contains(&cell))
+            .unwrap()
+    }
+}
+
+/// Builds manipulations that turn one board into another a few cells at a
+/// time, so that the work can be spread over several frames.
+pub struct SequenceBuilder {
+    moves: Moves,
+    cells: Cells,
+    target: Cells,
+    fixed: Vec<bool>,
+    manipulations: Vec<Manipulation>,
+    is_parity_fixed: bool,
+    /// Cells still to be brought into place, with whether it has to be done
+    /// with a cycle.
+    pending: VecDeque<(usize, bool)>,
+}
+
+impl SequenceBuilder {
+    /// Start building the manipulations from one board to another, which has
+    /// to be reachable from it.
+    pub fn new(from: &BoardState, to: &BoardState) -> Self {
+        Self::with_moves(Moves::new(from.size()), from, to)
+    }
+
+    fn with_moves(moves: Moves, from: &BoardState, to: &BoardState) -> Self {
+        let target = cells_of(to);
+
+        // Orbits with all pieces distinct can only be solved with cycles, because
+        // single pieces can't be placed without changing the parity of the orbit.
+        let (distinct, repeated): (Vec<_>, Vec<_>) = moves
+            .orbits
+            .iter()
+            .partition(|orbit| has_distinct_colors(orbit, &target));
+
+        let mut remaining: Vec<usize> = repeated.into_iter().flatten().copied().collect();
+        remaining.sort();
+
+        let pending = distinct
+            .into_iter()
+            .flatten()
+            .map(|cell| (*cell, true))
+            .chain(remaining.into_iter().map(|cell| (cell, false)))
+            .collect();
+
+        Self {
+            cells: cells_of(from),
+            target,
+            fixed: vec![false; from.size().position_count()],
+            manipulations: vec![],
+            is_parity_fixed: false,
+            pending,
+            moves,
+        }
+    }
+
+    /// Bring the next cell into place. Returns whether the whole board is done.
+    pub fn step(&mut self) -> bool {
+        if !self.is_parity_fixed {
+            self.fix_parity();
+            self.is_parity_fixed = true;
+        } else if let Some((cell, cycle_only)) = self.pending.pop_front() {
+            if cycle_only || !self.place(cell) {
+                self.cycle_into_place(cell);
+            }
+        }
+
+        self.is_parity_fixed && self.pending.is_empty()
+    }
+
+    /// The manipulations found so far, without the ones that cancel out.
+    pub fn manipulations(&self) -> Vec<Manipulation> {
+        simplify(&self.manipulations)
+    }
+
+    fn apply(&mut self, move_index: usize) {
+        self.cells = apply(&self.cells, &self.moves.destinations[move_index]);
+        self.manipulations
+            .push(self.moves.manipulations[move_index]);
+    }
+
+    /// Make the permutation needed within every orbit of distinct colors even,
+    /// so that it can be made with three piece cycles.
+    fn fix_parity(&mut self) {
+        for orbit_index in 0..self.moves.orbits.len() {
+            let orbit = &self.moves.orbits[orbit_index];
+            if !has_distinct_colors(orbit, &self.target)
+                || is_even(orbit, &self.cells, &self.target)
+            {
+                continue;
+            }
+
+            let odd_move = (0..self.moves.manipulations.len()).find(|index| {
+                let cells = apply(&self.cells, &self.moves.destinations[*index]);
+                is_even(orbit, &cells, &self.target)
+            });
+
+            if let Some(index) = odd_move {
+                self.apply(index);
+            }
+        }
+    }
+
+    /// Bring the right color into the cell with a few manipulations that keep
+    /// all the fixed cells in place.
+    fn place(&mut self, cell: usize) -> bool {
+        for depth in 0..=MAX_PLACEMENT_DEPTH {
+            let mut path = vec![];
+            if self.search_placement(self.cells.clone(), cell, depth, &mut path) {
+                for index in path {
+                    self.apply(index);
+                }
+                self.fixed[cell] = true;
+                return true;
+            }
+        }
+
+        false
+    }
+
+    fn search_placement(
+        &self,
+        cells: Cells,
+        cell: usize,
+        depth: usize,
+        path: &mut Vec<usize>,
+    ) -> bool {
+        if depth == 0 {
+            return cells[cell] == self.target[cell]
+                && (0..cells.len()).all(|c| !self.fixed[c] || cells[c] == self.target[c]);
+        }
+
+        let manipulations = &self.moves.manipulations;
+
+        for index in 0..manipulations.len() {
+            let is_inverse_of_last = path
+                .last()
+                .is_some_and(|last| manipulations[*last].inverse() == manipulations[index]);
+
+            if is_inverse_of_last {
+                continue;
+            }
+
+            path.push(index);
+            let next = apply(&cells, &self.moves.destinations[index]);
+            if self.search_placement(next, cell, depth - 1, path) {
+                return true;
+            }
+            path.pop();
+        }
+
+        false
+    }
+
+    /// Bring the right color into the cell by cycling three cells of its orbit,
+    /// which keeps every other cell in place.
+    fn cycle_into_place(&mut self, cell: usize) {
+        if self.cells[cell] != self.target[cell] {
+            let orbit = self.moves.orbit_of(cell);
+            let is_free = |c: usize| c != cell && !self.fixed[c];
+
+            // The piece from the source moves into the cell, the piece from the
+            // cell moves into the third cell and the piece from the third cell
+            // moves into the source. A fixed cell can only be the third one if
+            // it gets the same color back. Prefer cycles that fix more cells.
+            let (source, third) = orbit
+                .iter()
+                .filter(|source| is_free(**source) && self.cells[**source] == self.target[cell])
+                .flat_map(|source| orbit.iter().map(move |third| (*source, *third)))
+                .filter(|(source, third)| {
+                    *third != cell
+                        && third != source
+                        && (is_free(*third) || self.target[*third] == self.cells[cell])
+                })
+                .max_by_key(|(source, third)| {
+                    let third_fixed = self.target[*third] == self.cells[cell];
+                    let source_fixed = self.target[*source] == self.cells[*third];
+                    (third_fixed as u8 + source_fixed as u8, is_free(*third))
+                })
+                .unwrap();
+
+            for index in self.conjugated_cycle([source, cell, third]) {
+                self.apply(index);
+            }
+        }
+
+        self.fixed[cell] = true;
+    }
+
+    /// Find manipulations that move the piece at `a` to `b`, `b` to `c` and `c`
+    /// to `a`, by moving the three cells into the place of a known cycle first.
+    fn conjugated_cycle(&self, [a, b, c]: [usize; 3]) -> Vec<usize> {
+        let count = self.cells.len();
+        let encode = |[a, b, c]: [usize; 3]| (a * count + b) * count + c;
+
+        let mut previous = HashMap::from([(encode([a, b, c]), (usize::MAX, usize::MAX))]);
+        let mut queue = VecDeque::from([[a, b, c]]);
+
+        while let Some(triple) = queue.pop_front() {
+            let cycle = self.moves.cycles.iter().find(|([p, q, r], _)| {
+                [[*p, *q, *r], [*q, *r, *p], [*r, *p, *q]].contains(&triple)
+            });
+
+            if let Some((_, sequence)) = cycle {
+                let mut setup = vec![];
+                let mut state = encode(triple);
+                while let Some(&(from, index)) =
+                    previous.get(&state).filter(|(from, _)| *from != usize::MAX)
+                {
+                    setup.push(index);
+                    state = from;
+                }
+                setup.reverse();
+
+                let undo_setup = setup
+                    .iter()
+                    .rev()
+                    .map(|index| inverse_index(&self.moves.manipulations, *index));
+                return setup
+                    .iter()
+                    .copied()
+                    .chain(sequence.iter().copied())
+                    .chain(undo_setup)
+                    .collect();
+            }
+
+            for (index, destinations) in self.moves.destinations.iter().enumerate() {
+                let next = triple.map(|cell| destinations[cell]);
+                if let Entry::Vacant(entry) = previous.entry(encode(next)) {
+                    entry.insert((encode(triple), index));
+                    queue.push_back(next);
+                }
+            }
+        }
+
+        unreachable!("Cells of the same orbit can always be cycled")
+    }
+}
+
+fn destinations_of(manipulation: Manipulation, size: BoardSize) -> Destinations {
+    let mut destinations = vec![0; size.position_count()];
+
+    for position in Position::all(size) {
+        let mut new_position = position;
+
+        if manipulation.affects(&position) {
+            new_position
+                .apply_manipulation(manipulation.piece_manipulation())
+                .unwrap();
+        }
+
+        destinations[position.index()] = new_position.index();
+    }
+
+    destinations
+}
+
+fn orbits_of(destinations: &[Destinations]) -> Vec<Vec<usize>> {
+    let count = destinations[0].len();
+    let mut orbits: Vec<Vec<usize>> = vec![];
+    let mut visited = vec![false; count];
+
+    for start in 0..count {
+        if visited[start] {
+            continue;
+        }
+
+        let mut orbit = vec![start];
+        visited[start] = true;
+        let mut i = 0;
+
+        while i < orbit.len() {
+            for move_destinations in destinations {
+                let next = move_destinations[orbit[i]];
+                if !visited[next] {
+                    visited[next] = true;
+                    orbit.push(next);
+                }
+            }
+            i += 1;
+        }
+
+        orbit.sort();
+        orbits.push(orbit);
+    }
+
+    orbits
+}
+
+/// Find commutators of two manipulations that cycle exactly three cells.
+fn three_cycles(
+    destinations: &[Destinations],
+    manipulations: &[Manipulation],
+) -> Vec<([usize; 3], Vec<usize>)> {
+    let count = destinations[0].len();
+    let mut cycles = vec![];
+
+    for a in 0..destinations.len() {
+        for b in 0..destinations.len() {
+            let sequence = vec![
+                a,
+                b,
+                inverse_index(manipulations, a),
+                inverse_index(manipulations, b),
+            ];
+
+            let mut combined: Destinations = (0..count).collect();
+            for index in &sequence {
+                combined = combined
+                    .iter()
+                    .map(|cell| destinations[*index][*cell])
+                    .collect();
+            }
+
+            let moved: Vec<usize> = (0..count).filter(|cell| combined[*cell] != *cell).collect();
+
+            if moved.len() == 3 {
+                let p = moved[0];
+                let q = combined[p];
+                let r = combined[q];
+                cycles.push(([p, q, r], sequence));
+            }
+        }
+    }
+
+    cycles
+}
+
+fn inverse_index(manipulations: &[Manipulation], index: usize) -> usize {
+    let inverse = manipulations[index].inverse();
+    manipulations.iter().position(|m| *m == inverse).unwrap()
+}
+
+fn apply(cells: &Cells, destinations: &Destinations) -> Cells {
+    let mut result = cells.clone();
+    for (from, to) in destinations.iter().enumerate() {
+        result[*to] = cells[from];
+    }
+    result
+}
+
+fn cells_of(state: &BoardState) -> Cells {
+    state.colors().to_vec()
+}
+
+fn color_index(color: Color) -> usize {
+    Color::ALL.iter().position(|c| *c == color).unwrap()
+}
+
+fn has_distinct_colors(orbit: &[usize], cells: &Cells) -> bool {
+    let mut colors: Vec<usize> = orbit.iter().map(|c| color_index(cells[*c])).collect();
+    colors.sort();
+    colors.windows(2).all(|pair| pair[0] != pair[1])
+}
+
+/// Whether the permutation taking the colors of an orbit with distinct colors
+/// from `cells` to `target` is even.
+fn is_even(orbit: &[usize], cells: &Cells, target: &Cells) -> bool {
+    let permutation: Vec<usize> = orbit
+        .iter()
+        .map(|cell| {
+            orbit
+                .iter()
+                .position(|c| cells[*c] == target[*cell])
+                .unwrap()
+        })
+        .collect();
+
+    let mut visited = vec![false; permutation.len()];
+    let mut transpositions = 0;
+
+    for start in 0..permutation.len() {
+        let mut cycle_length = 0;
+        let mut i = start;
+        while !visited[i] {
+            visited[i] = true;
+            i = permutation[i];
+            cycle_length += 1;
+        }
+        transpositions += cycle_length.max(1) - 1;
+    }
+
+    transpositions % 2 == 0
+}
+
+/// Remove manipulations that cancel out and shorten repeated ones.
+fn simplify(manipulations: &[Manipulation]) -> Vec<Manipulation> {
+    let mut result = vec![];
+    for manipulation in manipulations {
+        push_simplified(&mut result, *manipulation);
+    }
+    result
+}
+
+fn push_simplified(result: &mut Vec<Manipulation>, manipulation: Manipulation) {
+    if result.last() == Some(&manipulation.inverse()) {
+        result.pop();
+        return;
+    }
+
+    // Two slides or three rotations in the same direction are the same as one
+    // in the other direction
+    let shorter_the_other_way = match manipulation {
+        Manipulation::RotateClockwise(_) | Manipulation::RotateAnticlockwise(_) => 3,
+        _ => 2,
+    };
+
+    let run = result
+        .iter()
+        .rev()
+        .take_while(|m| **m == manipulation)
+        .count();
+
+    if run + 1 == shorter_the_other_way {
+        result.truncate(result.len() - run);
+        push_simplified(result, manipulation.inverse());
+    } else {
+        result.push(manipulation);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_case::test_case;
+
+    #[test]
+    fn same_seed_produces_the_same_scramble() {
+        let first = generate_shuffle_manipulations(42, 20, BoardSize::STANDARD, &Ruleset::Standard);
+        let second =
+            generate_shuffle_manipulations(42, 20, BoardSize::STANDARD, &Ruleset::Standard);
+
+        assert_eq!(first, second);
+    }
+
+    #[test]
+    fn shuffle_works_with_a_single_pair_of_manipulations() {
+        use Manipulation::*;
+
+        let manipulations = shuffle(&[SlideLeft(3), SlideRight(3)], 42, 20);
+
+        assert_eq!(manipulations.len(), 20);
+        assert!(shuffle(&[], 42, 20).is_empty());
+    }
+
+    #[test]
+    fn different_seeds_produce_different_scrambles() {
+        let first = generate_shuffle_manipulations(1, 20, BoardSize::STANDARD, &Ruleset::Standard);
+        let second = generate_shuffle_manipulations(2, 20, BoardSize::STANDARD, &Ruleset::Standard);
+
+        assert_ne!(first, second);
+    }
+
+    #[test]
+    fn scramble_has_no_repeated_or_cancelling_manipulations() {
+        let manipulations =
+            generate_shuffle_manipulations(7, 100, BoardSize::STANDARD, &Ruleset::Standard);
+
+        assert_eq!(manipulations.len(), 100);
+        for pair in manipulations.windows(2) {
+            assert_ne!(pair[0], pair[1]);
+            assert_ne!(pair[0], pair[1].inverse());
+        }
+    }
+
+    #[test_case(1)]
+    #[test_case(42)]
+    #[test_case(1234567)]
+    #[test_case(1_700_000_000_000)]
+    fn random_state_manipulations_reach_the_sampled_state(seed: u64) {
+        let moves = Moves::new(BoardSize::STANDARD);
+        let expected = random_reachable_state(&moves, seed);
+
+        let mut state = BoardState::default();
+        for manipulation in generate_random_state_manipulations(seed, BoardSize::STANDARD) {
+            state.apply(manipulation).unwrap();
+        }
+
+        assert_eq!(state, expected);
+        assert!(is_reachable(&state));
+    }
+
+    // The builder is only checked by a debug assertion, so replay it on every
+    // size. A seed takes about 280 ms on the largest board in a release build.
+    #[test_case(2, 7)]
+    #[test_case(2, 42)]
+    #[test_case(2, 1_700_000_000_000)]
+    #[test_case(4, 7)]
+    #[test_case(4, 42)]
+    #[test_case(4, 1_700_000_000_000)]
+    #[test_case(5, 7)]
+    #[test_case(5, 42)]
+    #[test_case(5, 1_700_000_000_000)]
+    fn random_state_manipulations_work_on_every_board_size(square_size: u8, seed: u64) {
+        let size = BoardSize::new(square_size).unwrap();
+        let moves = Moves::new(size);
+        let expected = random_reachable_state(&moves, seed);
+
+        let mut state = BoardState::new(size);
+        for manipulation in generate_random_state_manipulations(seed, size) {
+            state.apply(manipulation).unwrap();
+        }
+
+        assert_eq!(state, expected);
+        assert!(is_reachable(&state));
+    }
+
+    #[test]
+    fn random_state_is_the_same_for_the_same_seed() {
+        let moves = Moves::new(BoardSize::STANDARD);
+
+        assert_eq!(
+            random_reachable_state(&moves, 5),
+            random_reachable_state(&moves, 5)
+        );
+        assert_ne!(
+            random_reachable_state(&moves, 5),
+            random_reachable_state(&moves, 6)
+        );
+    }
+
+    #[test]
+    fn board_has_corner_edge_and_center_orbits() {
+        let moves = Moves::new(BoardSize::STANDARD);
+
+        let mut sizes: Vec<usize> = moves.orbits.iter().map(|orbit| orbit.len()).collect();
+        sizes.sort();
+
+        assert_eq!(sizes, vec![5, 20, 20]);
+    }
+
+    #[test]
+    fn swapping_a_corner_and_an_edge_is_not_reachable() {
+        // North square is solved, so swap a green corner with a purple edge
+        let mut colors = BoardState::default().colors().to_vec();
+        let corner = Position::new(BoardSize::STANDARD, 0, 3).unwrap().index();
+        let edge = Position::new(BoardSize::STANDARD, 4, 0).unwrap().index();
+        colors.swap(corner, edge);
+
+        let state = BoardState::from_colors(&colors).unwrap();
+
+        assert!(!is_reachable(&state));
+        assert!(is_reachable(&BoardState::default()));
+    }
+
+    #[test_case(&[Manipulation::SlideLeft(3), Manipulation::SlideRight(3)], &[]; "inverse")]
+    #[test_case(&[Manipulation::SlideUp(4), Manipulation::SlideUp(4)], &[Manipulation::SlideDown(4)]; "two slides")]
+    #[test_case(
+        &[Manipulation::RotateClockwise(1), Manipulation::RotateClockwise(1), Manipulation::RotateClockwise(1)],
+        &[Manipulation::RotateAnticlockwise(1)];
+        "three rotations"
+    )]
+    #[test_case(
+        &[Manipulation::SlideLeft(5), Manipulation::SlideUp(3), Manipulation::SlideDown(3), Manipulation::SlideLeft(5)],
+        &[Manipulation::SlideRight(5)];
+        "cascading"
+    )]
+    fn simplify_shortens_sequences(input: &[Manipulation], expected: &[Manipulation]) {
+        assert_eq!(simplify(input), expected);
+    }
+}
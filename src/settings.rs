@@ -0,0 +1,379 @@
+//! Player preferences that are kept between games.
+//!
+//! The settings are stored as lines of `<name> = <value>`. Unknown or
+//! malformed lines are skipped and missing settings keep their defaults.
+//! They can be changed in game on the settings screen, see [crate::menu].
+
+use std::io;
+
+use crate::{ruleset::Ruleset, storage::Storage, BoardSize, Easing};
+
+const STORAGE_KEY: &str = "settings";
+
+/// How the board, the buttons and the pieces are drawn.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Renderer {
+    /// With the images of the theme.
+    #[default]
+    Bitmap,
+    /// With shapes, see [crate::vector].
+    Vector,
+}
+
+impl Renderer {
+    fn name(&self) -> &'static str {
+        match self {
+            Renderer::Bitmap => "bitmap",
+            Renderer::Vector => "vector",
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "bitmap" => Some(Renderer::Bitmap),
+            "vector" => Some(Renderer::Vector),
+            _ => None,
+        }
+    }
+
+    /// The other renderer.
+    pub fn toggled(&self) -> Self {
+        match self {
+            Renderer::Bitmap => Renderer::Vector,
+            Renderer::Vector => Renderer::Bitmap,
+        }
+    }
+}
+
+/// How fast the moves made by the player are animated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum AnimationSpeed {
+    Slow,
+    #[default]
+    Normal,
+    Fast,
+}
+
+impl AnimationSpeed {
+    /// Time it takes to animate a whole manipulation, in seconds.
+    pub fn animation_length(&self) -> f64 {
+        match self {
+            AnimationSpeed::Slow => 0.6,
+            AnimationSpeed::Normal => 0.35,
+            AnimationSpeed::Fast => 0.2,
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            AnimationSpeed::Slow => "slow",
+            AnimationSpeed::Normal => "normal",
+            AnimationSpeed::Fast => "fast",
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "slow" => Some(AnimationSpeed::Slow),
+            "normal" => Some(AnimationSpeed::Normal),
+            "fast" => Some(AnimationSpeed::Fast),
+            _ => None,
+        }
+    }
+
+    /// The next faster speed, going back to the slowest after the fastest.
+    pub fn next(&self) -> Self {
+        match self {
+            AnimationSpeed::Slow => AnimationSpeed::Normal,
+            AnimationSpeed::Normal => AnimationSpeed::Fast,
+            AnimationSpeed::Fast => AnimationSpeed::Slow,
+        }
+    }
+}
+
+/// How the board is scrambled at the start of a game.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Scramble {
+    /// A board sampled uniformly from all the reachable ones, see
+    /// [crate::scramble::generate_random_state_manipulations].
+    #[default]
+    RandomState,
+    /// The given number of random manipulations.
+    Moves(usize),
+}
+
+impl Scramble {
+    /// Move counts that [Scramble::next] goes through.
+    const MOVE_COUNTS: [usize; 3] = [10, 20, 50];
+
+    fn name(&self) -> String {
+        match self {
+            Scramble::RandomState => "random".into(),
+            Scramble::Moves(count) => count.to_string(),
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "random" => Some(Scramble::RandomState),
+            count => count
+                .parse()
+                .ok()
+                .filter(|count| *count > 0)
+                .map(Scramble::Moves),
+        }
+    }
+
+    /// The next longer scramble, going back to the short ones after the
+    /// random state.
+    pub fn next(&self) -> Self {
+        let next_count = |count: usize| {
+            Self::MOVE_COUNTS
+                .into_iter()
+                .find(|preset| *preset > count)
+                .map_or(Scramble::RandomState, Scramble::Moves)
+        };
+
+        match *self {
+            Scramble::RandomState => Scramble::Moves(Self::MOVE_COUNTS[0]),
+            Scramble::Moves(count) => next_count(count),
+        }
+    }
+}
+
+/// How loud the sounds are, in steps of a quarter of the full volume.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Volume(u8);
+
+impl Volume {
+    const STEPS: u8 = 4;
+    pub const OFF: Volume = Volume(0);
+
+    /// Volume to play the sounds with, from 0 to 1.
+    pub fn gain(&self) -> f32 {
+        self.0 as f32 / Self::STEPS as f32
+    }
+
+    pub fn percent(&self) -> u8 {
+        self.0 * (100 / Self::STEPS)
+    }
+
+    fn name(&self) -> String {
+        self.percent().to_string()
+    }
+
+    fn from_name(name: &str) -> Option<Self> {
+        let percent: u8 = name.parse().ok()?;
+        let step = 100 / Self::STEPS;
+
+        (percent <= 100 && percent.is_multiple_of(step)).then(|| Volume(percent / step))
+    }
+
+    /// The next louder volume, going back to silent after the loudest.
+    pub fn next(&self) -> Self {
+        Volume((self.0 + 1) % (Self::STEPS + 1))
+    }
+}
+
+impl Default for Volume {
+    fn default() -> Self {
+        Volume(Self::STEPS / 2)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Settings {
+    /// Draw a symbol on every piece, so that colors don't have to be told
+    /// apart by hue alone.
+    pub colorblind_mode: bool,
+    /// Volume of the sounds of the moves and of solving the board.
+    pub volume: Volume,
+    pub renderer: Renderer,
+    pub animation_speed: AnimationSpeed,
+    /// Easing of the moves made by the player.
+    pub easing: Easing,
+    /// Play moves made while the pieces are still moving after each other,
+    /// instead of interrupting the moving pieces.
+    pub queue_moves: bool,
+    /// Speed up moves that were made while the pieces were still moving.
+    pub fast_mode: bool,
+    /// Takes effect on the next scramble.
+    pub scramble: Scramble,
+    /// Takes effect on the next game.
+    pub board_size: BoardSize,
+    /// Takes effect on the next game. Targets set their own board size.
+    pub ruleset: Ruleset,
+    /// Directory of the theme, see [crate::theme], or `None` for the
+    /// embedded art. Takes effect on the next game.
+    pub theme: Option<String>,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            colorblind_mode: false,
+            volume: Volume::default(),
+            renderer: Renderer::default(),
+            animation_speed: AnimationSpeed::default(),
+            easing: Easing::default(),
+            queue_moves: true,
+            fast_mode: false,
+            scramble: Scramble::default(),
+            board_size: BoardSize::default(),
+            ruleset: Ruleset::default(),
+            theme: None,
+        }
+    }
+}
+
+impl Settings {
+    pub fn load(storage: &dyn Storage) -> Self {
+        storage
+            .load(STORAGE_KEY)
+            .map(|text| Self::parse(&text))
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self, storage: &mut dyn Storage) -> io::Result<()> {
+        storage.save(STORAGE_KEY, &self.to_text())
+    }
+
+    fn parse(text: &str) -> Self {
+        let mut settings = Self::default();
+
+        for (name, value) in text.lines().filter_map(|line| line.split_once('=')) {
+            let value = value.trim();
+
+            match name.trim() {
+                "colorblind-mode" => {
+                    if let Ok(value) = value.parse() {
+                        settings.colorblind_mode = value;
+                    }
+                }
+                "volume" => {
+                    if let Some(volume) = Volume::from_name(value) {
+                        settings.volume = volume;
+                    }
+                }
+                "renderer" => {
+                    if let Some(renderer) = Renderer::from_name(value) {
+                        settings.renderer = renderer;
+                    }
+                }
+                "animation-speed" => {
+                    if let Some(speed) = AnimationSpeed::from_name(value) {
+                        settings.animation_speed = speed;
+                    }
+                }
+                "queue-moves" => {
+                    if let Ok(value) = value.parse() {
+                        settings.queue_moves = value;
+                    }
+                }
+                "fast-mode" => {
+                    if let Ok(value) = value.parse() {
+                        settings.fast_mode = value;
+                    }
+                }
+                "easing" => {
+                    if let Some(easing) = Easing::from_name(value) {
+                        settings.easing = easing;
+                    }
+                }
+                "scramble" => {
+                    if let Some(scramble) = Scramble::from_name(value) {
+                        settings.scramble = scramble;
+                    }
+                }
+                "board-size" => {
+                    if let Some(size) = value.parse().ok().and_then(|s| BoardSize::new(s).ok()) {
+                        settings.board_size = size;
+                    }
+                }
+                "ruleset" => {
+                    if let Some(ruleset) = Ruleset::from_name(value) {
+                        settings.ruleset = ruleset;
+                    }
+                }
+                "theme" => settings.theme = Some(value.to_string()).filter(|v| !v.is_empty()),
+                _ => (),
+            }
+        }
+
+        settings
+    }
+
+    fn to_text(&self) -> String {
+        [
+            format!("colorblind-mode = {}", self.colorblind_mode),
+            format!("volume = {}", self.volume.name()),
+            format!("renderer = {}", self.renderer.name()),
+            format!("animation-speed = {}", self.animation_speed.name()),
+            format!("easing = {}", self.easing.name()),
+            format!("queue-moves = {}", self.queue_moves),
+            format!("fast-mode = {}", self.fast_mode),
+            format!("scramble = {}", self.scramble.name()),
+            format!("board-size = {}", self.board_size.square_size()),
+            format!("ruleset = {}", self.ruleset.name()),
+            format!("theme = {}", self.theme.as_deref().unwrap_or_default()),
+        ]
+        .join("\n")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_case::test_case;
+
+    #[test]
+    fn settings_round_trip() {
+        let settings = Settings {
+            colorblind_mode: true,
+            volume: Volume::OFF,
+            renderer: Renderer::Vector,
+            animation_speed: AnimationSpeed::Fast,
+            easing: Easing::Bounce,
+            queue_moves: false,
+            fast_mode: true,
+            scramble: Scramble::Moves(30),
+            board_size: BoardSize::new(5).unwrap(),
+            ruleset: Ruleset::SlidesOnly,
+            theme: Some("themes/dark".into()),
+        };
+
+        assert_eq!(Settings::parse(&settings.to_text()), settings);
+    }
+
+    #[test]
+    fn malformed_settings_keep_defaults() {
+        let settings = Settings::parse(
+            "colorblind-mode = maybe\nrenderer = svg\nscramble = 0\nboard-size = 7\nruleset = target:GGG\ntheme =\nvolume\nvolume = 30\nunknown = 3",
+        );
+
+        assert_eq!(settings, Settings::default());
+    }
+
+    #[test_case(Scramble::RandomState, Scramble::Moves(10))]
+    #[test_case(Scramble::Moves(10), Scramble::Moves(20))]
+    #[test_case(Scramble::Moves(50), Scramble::RandomState)]
+    #[test_case(Scramble::Moves(30), Scramble::Moves(50); "custom count")]
+    fn scramble_cycles_through_presets(scramble: Scramble, expected: Scramble) {
+        assert_eq!(scramble.next(), expected);
+    }
+
+    #[test]
+    fn volume_gets_louder_and_then_silent() {
+        let mut volume = Volume::OFF;
+        let mut percents = vec![];
+
+        for _ in 0..=Volume::STEPS {
+            volume = volume.next();
+            percents.push(volume.percent());
+            assert_eq!(Volume::from_name(&volume.name()), Some(volume));
+        }
+
+        assert_eq!(percents, [25, 50, 75, 100, 0]);
+        assert_eq!(Volume::default().gain(), 0.5);
+    }
+}
@@ -1,4 +1,4 @@
-use crate::pieces::position;
+use crate::{pieces::position, settings::Renderer, vector, BoardSize};
 use macroquad::{
     color::WHITE,
     texture::{draw_texture_ex, DrawTextureParams, Texture2D},
@@ -11,6 +11,7 @@ pub struct SolvedMarker<'a> {
     idle_texture: &'a Texture2D,
     square: position::Square,
     rotation: f32,
+    size: BoardSize,
 }
 
 impl<'a> SolvedMarker<'a> {
@@ -20,6 +21,7 @@ impl<'a> SolvedMarker<'a> {
         square: position::Square,
         idle_texture: &'a Texture2D,
         rotation: f32,
+        size: BoardSize,
     ) -> Self {
         Self {
             x,
@@ -27,6 +29,7 @@ impl<'a> SolvedMarker<'a> {
             square,
             idle_texture,
             rotation,
+            size,
         }
     }
 
@@ -34,7 +37,14 @@ impl<'a> SolvedMarker<'a> {
         &self.square
     }
 
-    pub fn draw(&self) {
+    /// Draw the marker. Its image only fits the standard board, so it is drawn
+    /// with shapes on the others.
+    pub fn draw(&self, renderer: Renderer) {
+        if renderer == Renderer::Vector || self.size != BoardSize::STANDARD {
+            vector::draw_solved_marker(self.square, self.size);
+            return;
+        }
+
         draw_texture_ex(
             self.idle_texture,
             self.x,
@@ -0,0 +1,774 @@
+use std::{
+    collections::{HashSet, VecDeque},
+    error::Error,
+    fmt::Display,
+};
+
+use macroquad::miniquad::date::now;
+
+use crate::{
+    pieces::position::Square,
+    scramble::{self, SequenceBuilder},
+    BoardSize, BoardState, Color, Manipulation, Position,
+};
+
+const SQUARE_COUNT: usize = Square::ALL.len();
+const COLOR_COUNT: usize = Color::ALL.len();
+
+/// Nodes a [HintSearch] expands between checks of its time budget.
+const NODES_PER_CHECK: u64 = 500;
+
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum SolverError {
+    /// The board can't be reached from the solved one, so it can't be solved.
+    Unreachable,
+    NodeLimitReached {
+        nodes_expanded: u64,
+    },
+    /// Every sequence of manipulations was ruled out without finding one that
+    /// solves the board.
+    SearchExhausted {
+        nodes_expanded: u64,
+    },
+}
+
+impl Display for SolverError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SolverError::Unreachable => {
+                write!(f, "The board can't be reached from the solved board")
+            }
+            SolverError::NodeLimitReached { nodes_expanded } => write!(
+                f,
+                "No solution found after expanding {} nodes",
+                nodes_expanded
+            ),
+            SolverError::SearchExhausted { nodes_expanded } => write!(
+                f,
+                "No solution exists, every sequence was ruled out after expanding {} nodes",
+                nodes_expanded
+            ),
+        }
+    }
+}
+
+impl Error for SolverError {}
+
+#[derive(Debug)]
+pub struct Solution {
+    pub manipulations: Vec<Manipulation>,
+    pub nodes_expanded: u64,
+    /// Time spent searching, in seconds.
+    pub duration: f64,
+}
+
+type Cells = Vec<Color>;
+
+struct Move {
+    manipulation: Manipulation,
+    /// For every cell, the index of the cell its piece comes from.
+    sources: Vec<usize>,
+    affected: Vec<bool>,
+    /// Whether applying the same move twice in a row can be part of an optimal solution.
+    can_repeat: bool,
+}
+
+struct Search {
+    size: BoardSize,
+    moves: Vec<Move>,
+    squares: Vec<usize>,
+    path: Vec<usize>,
+    nodes_expanded: u64,
+    max_nodes: Option<u64>,
+    /// Moves that the solution can't start with.
+    excluded_first_moves: Vec<bool>,
+    /// The board after the moves of the path.
+    cells: Cells,
+    /// The board before every move of the path, to take the moves back.
+    previous_cells: Vec<Cells>,
+    /// For the start and every move of the path, the next move to try after it.
+    next_moves: Vec<usize>,
+    bound: usize,
+    next_bound: usize,
+}
+
+enum Outcome {
+    Found,
+    Paused,
+    /// The node limit was reached.
+    Aborted,
+    /// Every path was tried.
+    Exhausted,
+}
+
+/// Looks for the manipulation to suggest to the player a part at a time, so
+/// that the game keeps running while it searches.
+///
+/// A shortest solution is searched for first. Boards that are too far from
+/// solved for that within the node limit get a plan that solves them piece by
+/// piece instead. The plan is kept while the player follows it, so following
+/// the hints always leads to a solved board. A hint never leads back to a board
+/// the player has already been at.
+pub struct HintSearch {
+    max_nodes: u64,
+    /// The board the plan starts from and its manipulations.
+    plan: Option<(BoardState, VecDeque<Manipulation>)>,
+    /// The board a hint was asked for and the work left to find it.
+    pending: Option<(BoardState, HintTask)>,
+}
+
+enum HintTask {
+    Search(Search),
+    /// Building a plan from the board after the manipulation, if there is one.
+    Build(Option<Manipulation>, SequenceBuilder),
+}
+
+/// Find a shortest sequence of manipulations that solves the given board.
+/// The state of a [crate::PieceCollection] can be obtained with
+/// [crate::PieceCollection::state].
+///
+/// The search is unbounded, so it can take a very long time for deep scrambles.
+/// Boards that can't be reached from the solved one are refused.
+pub fn solve(state: &BoardState) -> Result<Solution, SolverError> {
+    search(state, None)
+}
+
+/// Same as [solve], but give up after expanding `max_nodes` nodes.
+pub fn solve_within(state: &BoardState, max_nodes: u64) -> Result<Solution, SolverError> {
+    search(state, Some(max_nodes))
+}
+
+impl HintSearch {
+    /// `max_nodes` limits the search for a shortest solution.
+    pub fn new(max_nodes: u64) -> Self {
+        Self {
+            max_nodes,
+            plan: None,
+            pending: None,
+        }
+    }
+
+    /// Start looking for a hint for the board. Any hint that is still being
+    /// looked for is given up. Boards that can't be solved get no hint.
+    pub fn start(&mut self, state: &BoardState, visited: &HashSet<BoardState>) {
+        if !scramble::is_reachable(state) {
+            self.pending = None;
+            return;
+        }
+
+        let mut search = Search::new(state, Some(self.max_nodes));
+        search.excluded_first_moves = search
+            .moves
+            .iter()
+            .map(|m| visited.contains(&applied(state, m.manipulation)))
+            .collect();
+
+        self.pending = Some((state.clone(), HintTask::Search(search)));
+    }
+
+    /// Continue looking for the hint for about `time_budget` seconds. Returns
+    /// the hint once it's found. Nothing is returned while searching, and the
+    /// search is given up if the board isn't the one the hint was asked for.
+    /// The hint never leads to one of the `visited` boards.
+    pub fn poll(
+        &mut self,
+        state: &BoardState,
+        visited: &HashSet<BoardState>,
+        time_budget: f64,
+    ) -> Option<Manipulation> {
+        let (start, _) = self.pending.as_ref()?;
+        if start != state || state.is_solved() {
+            self.pending = None;
+            return None;
+        }
+
+        if let Some(hint) = self.planned_hint(state, visited) {
+            self.pending = None;
+            return Some(hint);
+        }
+
+        let deadline = now() + time_budget;
+
+        while now() < deadline {
+            let (_, task) = self.pending.as_mut()?;
+
+            let plan = match task {
+                HintTask::Search(search) => match search.run(NODES_PER_CHECK) {
+                    Outcome::Found => search.solution(),
+                    Outcome::Paused => continue,
+                    Outcome::Aborted | Outcome::Exhausted => {
+                        let solved = BoardState::new(state.size());
+                        *task = HintTask::Build(None, SequenceBuilder::new(state, &solved));
+                        continue;
+                    }
+                },
+                HintTask::Build(first, builder) => {
+                    if !builder.step() {
+                        continue;
+                    }
+
+                    let plan: Vec<_> = first
+                        .iter()
+                        .copied()
+                        .chain(builder.manipulations())
+                        .collect();
+                    let plan = without_loops(state, &plan);
+
+                    // Start from a board that wasn't visited if the plan doesn't
+                    if first.is_none()
+                        && plan
+                            .first()
+                            .is_some_and(|m| visited.contains(&applied(state, *m)))
+                    {
+                        if let Some(first) = unvisited_neighbor(state, visited) {
+                            let solved = BoardState::new(state.size());
+                            let builder = SequenceBuilder::new(&applied(state, first), &solved);
+                            *task = HintTask::Build(Some(first), builder);
+                            continue;
+                        }
+                    }
+
+                    plan
+                }
+            };
+
+            self.pending = None;
+            self.plan = Some((state.clone(), plan.into()));
+            return self.plan.as_ref()?.1.front().copied();
+        }
+
+        None
+    }
+
+    /// The next manipulation of the plan, if the board is on it.
+    fn planned_hint(
+        &mut self,
+        state: &BoardState,
+        visited: &HashSet<BoardState>,
+    ) -> Option<Manipulation> {
+        let (plan_start, plan) = self.plan.as_mut()?;
+
+        // Skip the part of the plan the player has already followed
+        while plan_start != state {
+            let Some(manipulation) = plan.pop_front() else {
+                break;
+            };
+            plan_start.apply(manipulation).unwrap();
+        }
+
+        let hint = plan.front().copied();
+        let is_on_plan = plan_start == state;
+
+        match hint {
+            Some(hint) if is_on_plan && !visited.contains(&applied(state, hint)) => Some(hint),
+            _ => {
+                self.plan = None;
+                None
+            }
+        }
+    }
+}
+
+/// A number of manipulations that is guaranteed not to exceed the length of
+/// the shortest solution.
+pub fn lower_bound(state: &BoardState) -> usize {
+    let cells = cells_of(state);
+    heuristic(&cells, &square_indices(state.size()), state.size())
+}
+
+fn search(state: &BoardState, max_nodes: Option<u64>) -> Result<Solution, SolverError> {
+    // The search would never end for a board without a solution
+    if !scramble::is_reachable(state) {
+        return Err(SolverError::Unreachable);
+    }
+
+    let start = now();
+
+    let mut search = Search::new(state, max_nodes);
+
+    match search.run(u64::MAX) {
+        Outcome::Found => Ok(Solution {
+            manipulations: search.solution(),
+            nodes_expanded: search.nodes_expanded,
+            duration: now() - start,
+        }),
+        Outcome::Paused | Outcome::Aborted => Err(SolverError::NodeLimitReached {
+            nodes_expanded: search.nodes_expanded,
+        }),
+        Outcome::Exhausted => Err(SolverError::SearchExhausted {
+            nodes_expanded: search.nodes_expanded,
+        }),
+    }
+}
+
+/// The board after the manipulation.
+fn applied(state: &BoardState, manipulation: Manipulation) -> BoardState {
+    let mut next = state.clone();
+    next.apply(manipulation).unwrap();
+    next
+}
+
+/// The manipulation to a board that isn't one of the `visited` boards and is
+/// the closest to solved by [lower_bound].
+fn unvisited_neighbor(state: &BoardState, visited: &HashSet<BoardState>) -> Option<Manipulation> {
+    Manipulation::all(state.size())
+        .into_iter()
+        .filter(|manipulation| !visited.contains(&applied(state, *manipulation)))
+        .min_by_key(|manipulation| lower_bound(&applied(state, *manipulation)))
+}
+
+/// Leave out the parts of the manipulations that come back to a board they
+/// have already gone through.
+fn without_loops(state: &BoardState, manipulations: &[Manipulation]) -> Vec<Manipulation> {
+    let mut states = vec![state.clone()];
+    let mut result = vec![];
+
+    for manipulation in manipulations {
+        let next = applied(states.last().unwrap(), *manipulation);
+
+        match states.iter().position(|s| *s == next) {
+            Some(index) => {
+                states.truncate(index + 1);
+                result.truncate(index);
+            }
+            None => {
+                states.push(next);
+                result.push(*manipulation);
+            }
+        }
+    }
+
+    result
+}
+
+impl Move {
+    fn new(manipulation: Manipulation, size: BoardSize) -> Self {
+        let mut sources = vec![0; size.position_count()];
+        let mut affected = vec![false; size.position_count()];
+
+        for position in Position::all(size) {
+            let mut new_position = position;
+
+            if manipulation.affects(&position) {
+                new_position
+                    .apply_manipulation(manipulation.piece_manipulation())
+                    .unwrap();
+                affected[position.index()] = true;
+            }
+
+            sources[new_position.index()] = position.index();
+        }
+
+        // Sliding twice is the same as sliding once in the other direction
+        let can_repeat = matches!(
+            manipulation,
+            Manipulation::RotateClockwise(_) | Manipulation::RotateAnticlockwise(_)
+        );
+
+        Self {
+            manipulation,
+            sources,
+            affected,
+            can_repeat,
+        }
+    }
+
+    fn apply(&self, cells: &Cells) -> Cells {
+        self.sources.iter().map(|source| cells[*source]).collect()
+    }
+
+    fn commutes_with(&self, other: &Move) -> bool {
+        !self
+            .affected
+            .iter()
+            .zip(other.affected.iter())
+            .any(|(a, b)| *a && *b)
+    }
+}
+
+impl Search {
+    fn new(state: &BoardState, max_nodes: Option<u64>) -> Self {
+        let size = state.size();
+        let moves: Vec<Move> = Manipulation::all(size)
+            .into_iter()
+            .map(|m| Move::new(m, size))
+            .collect();
+        let squares = square_indices(size);
+        let cells = cells_of(state);
+
+        Self {
+            size,
+            excluded_first_moves: vec![false; moves.len()],
+            moves,
+            bound: heuristic(&cells, &squares, size),
+            next_bound: usize::MAX,
+            squares,
+            path: vec![],
+            nodes_expanded: 0,
+            max_nodes,
+            cells,
+            previous_cells: vec![],
+            next_moves: vec![],
+        }
+    }
+
+    /// Continue the search until it's over or `nodes` more nodes are expanded.
+    /// The search is an iterative deepening A*, made without recursion so
+    /// that it can be paused.
+    fn run(&mut self, nodes: u64) -> Outcome {
+        let pause_at = self.nodes_expanded.saturating_add(nodes);
+
+        loop {
+            let depth = self.path.len();
+
+            // The board after the path is reached for the first time
+            if self.next_moves.len() == depth {
+                let estimate = heuristic(&self.cells, &self.squares, self.size);
+
+                if estimate == 0 {
+                    return Outcome::Found;
+                }
+
+                if depth + estimate > self.bound {
+                    self.next_bound = self.next_bound.min(depth + estimate);
+                    self.take_back();
+                    continue;
+                }
+
+                if self.max_nodes.is_some_and(|max| self.nodes_expanded >= max) {
+                    return Outcome::Aborted;
+                }
+                if self.nodes_expanded >= pause_at {
+                    return Outcome::Paused;
+                }
+                self.nodes_expanded += 1;
+                self.next_moves.push(0);
+            }
+
+            let next_move = (self.next_moves[depth]..self.moves.len())
+                .find(|index| self.is_worth_trying(*index));
+
+            match next_move {
+                Some(index) => {
+                    self.next_moves[depth] = index + 1;
+                    let cells = self.moves[index].apply(&self.cells);
+                    self.previous_cells
+                        .push(std::mem::replace(&mut self.cells, cells));
+                    self.path.push(index);
+                }
+                None if depth > 0 => {
+                    self.next_moves.pop();
+                    self.take_back();
+                }
+                None => {
+                    // Every path within the bound has been tried
+                    if self.next_bound == usize::MAX {
+                        return Outcome::Exhausted;
+                    }
+                    self.next_moves.pop();
+                    self.bound = self.next_bound;
+                    self.next_bound = usize::MAX;
+                }
+            }
+        }
+    }
+
+    fn take_back(&mut self) {
+        self.path.pop();
+        if let Some(cells) = self.previous_cells.pop() {
+            self.cells = cells;
+        }
+    }
+
+    fn solution(&self) -> Vec<Manipulation> {
+        self.path
+            .iter()
+            .map(|index| self.moves[*index].manipulation)
+            .collect()
+    }
+
+    /// Skip moves that can't be a part of a shortest solution, given the moves
+    /// made so far.
+    fn is_worth_trying(&self, index: usize) -> bool {
+        let Some(&last) = self.path.last() else {
+            return !self.excluded_first_moves[index];
+        };
+
+        let candidate = &self.moves[index];
+        let previous = &self.moves[last];
+
+        if candidate.manipulation == previous.manipulation.inverse() {
+            return false;
+        }
+
+        if index == last {
+            if !candidate.can_repeat {
+                return false;
+            }
+
+            // Three quarter turns are the same as one in the other direction
+            let second_to_last = self.path.len().checked_sub(2).map(|i| self.path[i]);
+            return second_to_last != Some(index);
+        }
+
+        // Independent moves are only tried in one order
+        !(candidate.commutes_with(previous) && index < last)
+    }
+}
+
+fn cells_of(state: &BoardState) -> Cells {
+    let mut cells = vec![Color::Yellow; state.size().position_count()];
+    for (position, color) in state.iter() {
+        cells[position.index()] = color;
+    }
+    cells
+}
+
+fn square_indices(size: BoardSize) -> Vec<usize> {
+    let mut squares = vec![0; size.position_count()];
+    for position in Position::all(size) {
+        squares[position.index()] = Square::ALL
+            .iter()
+            .position(|square| *square == position.square())
+            .unwrap();
+    }
+    squares
+}
+
+fn color_index(color: Color) -> usize {
+    Color::ALL.iter().position(|c| *c == color).unwrap()
+}
+
+/// Each square needs all its mismatched pieces replaced and a single
+/// manipulation can only move a limited number of pieces in and out of it.
+fn heuristic(cells: &Cells, squares: &[usize], size: BoardSize) -> usize {
+    let square_size = size.square_size() as usize;
+    let pieces_per_square = square_size * square_size;
+    // A manipulation moves at most one line of pieces within one square, and
+    // one line in each of the four squares it moves between
+    let max_change_per_square = square_size;
+    let max_change_per_manipulation = 4 * square_size;
+
+    let mut counts = [[0usize; COLOR_COUNT]; SQUARE_COUNT];
+    for (cell, square) in cells.iter().zip(squares.iter()) {
+        counts[*square][color_index(*cell)] += 1;
+    }
+
+    let mismatches = counts
+        .map(|square_counts| pieces_per_square - square_counts.iter().max().copied().unwrap_or(0));
+
+    let total: usize = mismatches.iter().sum();
+    let worst_square = mismatches.iter().max().copied().unwrap_or(0);
+
+    total
+        .div_ceil(max_change_per_manipulation)
+        .max(worst_square.div_ceil(max_change_per_square))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scramble::generate_random_state_manipulations;
+    use test_case::test_case;
+    use Manipulation::*;
+
+    fn scrambled(manipulations: &[Manipulation]) -> BoardState {
+        let mut state = BoardState::default();
+        for manipulation in manipulations {
+            state.apply(*manipulation).unwrap();
+        }
+        state
+    }
+
+    #[test]
+    fn solved_board_needs_no_manipulations() {
+        let solution = solve(&BoardState::default()).unwrap();
+
+        assert!(solution.manipulations.is_empty());
+        assert_eq!(lower_bound(&BoardState::default()), 0);
+    }
+
+    #[test_case(&[RotateClockwise(0)]; "single rotation")]
+    #[test_case(&[SlideDown(4)]; "single slide")]
+    #[test_case(&[SlideLeft(3), RotateAnticlockwise(2)]; "slide and rotation")]
+    #[test_case(&[RotateClockwise(1), SlideUp(5), SlideRight(4)]; "three manipulations")]
+    #[test_case(&[SlideUp(3), RotateClockwise(2), SlideLeft(5), RotateAnticlockwise(0)]; "four manipulations")]
+    fn solution_solves_the_board_optimally(scramble: &[Manipulation]) {
+        let mut state = scrambled(scramble);
+
+        let solution = solve(&state).unwrap();
+        for manipulation in &solution.manipulations {
+            state.apply(*manipulation).unwrap();
+        }
+
+        assert!(state.is_solved());
+        assert!(solution.manipulations.len() <= scramble.len());
+    }
+
+    #[test_case(2, &[SlideLeft(2), RotateClockwise(1)]; "smallest board")]
+    #[test_case(4, &[SlideUp(6), RotateAnticlockwise(3)]; "larger board")]
+    #[test_case(5, &[RotateClockwise(4), SlideRight(9)]; "largest board")]
+    fn boards_of_every_size_are_solved(square_size: u8, scramble: &[Manipulation]) {
+        let mut state = BoardState::new(BoardSize::new(square_size).unwrap());
+        for manipulation in scramble {
+            state.apply(*manipulation).unwrap();
+        }
+
+        let solution = solve(&state).unwrap();
+        for manipulation in &solution.manipulations {
+            state.apply(*manipulation).unwrap();
+        }
+
+        assert!(state.is_solved());
+        assert_eq!(solution.manipulations.len(), scramble.len());
+    }
+
+    #[test_case(&[RotateClockwise(0)], 1; "single rotation")]
+    #[test_case(&[RotateClockwise(2), RotateClockwise(2)], 2; "half turn")]
+    #[test_case(&[SlideRight(3), SlideDown(4)], 2; "two slides")]
+    fn solution_has_the_expected_length(scramble: &[Manipulation], expected: usize) {
+        let solution = solve(&scrambled(scramble)).unwrap();
+
+        assert_eq!(solution.manipulations.len(), expected);
+    }
+
+    #[test_case(&[RotateClockwise(1), SlideUp(5), SlideRight(4)])]
+    #[test_case(&[SlideUp(3), RotateClockwise(2), SlideLeft(5), RotateAnticlockwise(0)])]
+    fn lower_bound_does_not_exceed_solution_length(scramble: &[Manipulation]) {
+        let state = scrambled(scramble);
+
+        let solution = solve(&state).unwrap();
+
+        assert!(lower_bound(&state) <= solution.manipulations.len());
+    }
+
+    /// Follow the hints from the board until it's solved, returning the
+    /// number of moves made.
+    fn follow_hints(
+        mut state: BoardState,
+        visited: &mut HashSet<BoardState>,
+        max_moves: usize,
+    ) -> usize {
+        let mut hints = HintSearch::new(10_000);
+        let mut moves = 0;
+
+        while !state.is_solved() {
+            assert!(moves < max_moves, "hints didn't solve the board");
+
+            hints.start(&state, visited);
+            let hint = hints.poll(&state, visited, f64::INFINITY).unwrap();
+
+            visited.insert(state.clone());
+            state.apply(hint).unwrap();
+            assert!(!visited.contains(&state));
+            moves += 1;
+        }
+
+        moves
+    }
+
+    #[test]
+    fn hint_starts_an_optimal_solution() {
+        let state = scrambled(&[SlideLeft(4), RotateClockwise(0)]);
+
+        assert_eq!(follow_hints(state, &mut HashSet::new(), 2), 2);
+    }
+
+    #[test]
+    fn hint_does_not_undo_the_last_move() {
+        let previous = scrambled(&[SlideLeft(4), RotateClockwise(0)]);
+        let state = applied(&previous, SlideUp(3));
+        let visited = HashSet::from([previous]);
+
+        let mut hints = HintSearch::new(10_000);
+        hints.start(&state, &visited);
+        let hint = hints.poll(&state, &visited, f64::INFINITY).unwrap();
+
+        assert_ne!(hint, SlideDown(3));
+        follow_hints(state, &mut visited.clone(), 50);
+    }
+
+    #[test_case(1)]
+    #[test_case(42)]
+    #[test_case(1234567)]
+    #[test_case(1_700_000_000_000)]
+    fn hints_solve_a_random_state(seed: u64) {
+        let size = BoardSize::STANDARD;
+        let mut state = BoardState::new(size);
+        for manipulation in generate_random_state_manipulations(seed, size) {
+            state.apply(manipulation).unwrap();
+        }
+
+        follow_hints(state, &mut HashSet::new(), 200);
+    }
+
+    #[test]
+    fn hint_is_not_given_for_another_board() {
+        let state = scrambled(&[SlideLeft(4)]);
+        let mut hints = HintSearch::new(10_000);
+
+        hints.start(&state, &HashSet::new());
+
+        assert!(hints
+            .poll(&BoardState::default(), &HashSet::new(), f64::INFINITY)
+            .is_none());
+        assert!(hints.poll(&state, &HashSet::new(), f64::INFINITY).is_none());
+    }
+
+    #[test]
+    fn search_can_be_paused() {
+        let state = scrambled(&[SlideUp(3), RotateClockwise(2), SlideLeft(5)]);
+        let mut search = Search::new(&state, None);
+
+        let mut pauses = 0;
+        while matches!(search.run(1), Outcome::Paused) {
+            pauses += 1;
+        }
+
+        assert!(pauses > 1);
+        assert_eq!(search.solution(), solve(&state).unwrap().manipulations);
+    }
+
+    #[test]
+    fn node_limit_aborts_the_search() {
+        let state = scrambled(&[SlideUp(3), RotateClockwise(2), SlideLeft(5)]);
+
+        let result = solve_within(&state, 1);
+
+        assert!(matches!(
+            result,
+            Err(SolverError::NodeLimitReached { nodes_expanded: 1 })
+        ));
+    }
+
+    #[test]
+    fn unreachable_board_is_refused() {
+        // Corners and edges of the squares never trade places
+        let size = BoardSize::STANDARD;
+        let corner = Position::new(size, 0, 3).unwrap().index();
+        let edge = Position::new(size, 3, 4).unwrap().index();
+        let mut colors = BoardState::new(size).colors().to_vec();
+        colors.swap(corner, edge);
+        let state = BoardState::from_colors(&colors).unwrap();
+
+        assert!(matches!(solve(&state), Err(SolverError::Unreachable)));
+        assert!(matches!(
+            solve_within(&state, 1000),
+            Err(SolverError::Unreachable)
+        ));
+
+        let mut hints = HintSearch::new(10_000);
+        hints.start(&state, &HashSet::new());
+        assert!(hints.poll(&state, &HashSet::new(), f64::INFINITY).is_none());
+    }
+
+    #[test]
+    fn search_without_allowed_first_moves_is_exhausted() {
+        let state = scrambled(&[SlideLeft(4)]);
+        let mut search = Search::new(&state, Some(1000));
+        search.excluded_first_moves = vec![true; search.moves.len()];
+
+        assert!(matches!(search.run(u64::MAX), Outcome::Exhausted));
+    }
+}
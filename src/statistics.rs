@@ -0,0 +1,279 @@
+use std::io;
+
+use crate::{ruleset::Ruleset, storage::Storage, BoardSize};
+
+const STORAGE_KEY: &str = "statistics";
+const HEADER: &str = "seed,moves,time,hints,date,size,rules";
+/// Headers of the statistics from before the board size and the rules were
+/// recorded.
+const OLD_HEADERS: [&str; 2] = [
+    "seed,moves,time,hints,date",
+    "seed,moves,time,hints,date,size",
+];
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Solve {
+    pub seed: u64,
+    pub moves: usize,
+    /// Time from the first move until solved, in seconds.
+    pub time: f64,
+    pub hints: usize,
+    /// Seconds since the Unix epoch.
+    pub date: f64,
+    pub size: BoardSize,
+    pub ruleset: Ruleset,
+}
+
+impl Solve {
+    fn to_line(&self) -> String {
+        format!(
+            "{},{},{},{},{},{},{}",
+            self.seed,
+            self.moves,
+            self.time,
+            self.hints,
+            self.date,
+            self.size.square_size(),
+            self.ruleset.name()
+        )
+    }
+
+    fn from_line(line: &str) -> Option<Self> {
+        let mut fields = line.split(',');
+
+        let solve = Solve {
+            seed: fields.next()?.parse().ok()?,
+            moves: fields.next()?.parse().ok()?,
+            time: fields.next()?.parse().ok()?,
+            hints: fields.next()?.parse().ok()?,
+            date: fields.next()?.parse().ok()?,
+            // Solves from before the board size was recorded are on the standard board
+            size: match fields.next() {
+                Some(size) => BoardSize::new(size.parse().ok()?).ok()?,
+                None => BoardSize::STANDARD,
+            },
+            // And before the rules were recorded, by the standard rules
+            ruleset: match fields.next() {
+                Some(name) => Ruleset::from_name(name)?,
+                None => Ruleset::Standard,
+            },
+        };
+
+        fields.next().is_none().then_some(solve)
+    }
+}
+
+/// History of all the completed solves.
+#[derive(Debug, Default, PartialEq)]
+pub struct Statistics {
+    solves: Vec<Solve>,
+}
+
+impl Statistics {
+    /// Load the statistics from the storage, skipping any malformed solves.
+    pub fn load(storage: &dyn Storage) -> Self {
+        let solves = storage
+            .load(STORAGE_KEY)
+            .map(|text| {
+                text.lines()
+                    .filter(|line| *line != HEADER && !OLD_HEADERS.contains(line))
+                    .filter_map(Solve::from_line)
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        Self { solves }
+    }
+
+    pub fn save(&self, storage: &mut dyn Storage) -> io::Result<()> {
+        let mut text = String::from(HEADER);
+        for solve in &self.solves {
+            text.push('\n');
+            text.push_str(&solve.to_line());
+        }
+
+        storage.save(STORAGE_KEY, &text)
+    }
+
+    pub fn record(&mut self, solve: Solve) {
+        self.solves.push(solve);
+    }
+
+    pub fn solves(&self) -> &[Solve] {
+        &self.solves
+    }
+
+    /// The solves on boards of the same size and by the same rules as the
+    /// solve, which bests and averages are only comparable within.
+    pub fn comparable_to(&self, solve: &Solve) -> Self {
+        let solves = self
+            .solves
+            .iter()
+            .filter(|other| other.size == solve.size && other.ruleset == solve.ruleset)
+            .cloned()
+            .collect();
+
+        Self { solves }
+    }
+
+    pub fn solve_count(&self) -> usize {
+        self.solves.len()
+    }
+
+    pub fn best_time(&self) -> Option<f64> {
+        self.solves.iter().map(|solve| solve.time).reduce(f64::min)
+    }
+
+    pub fn best_moves(&self) -> Option<usize> {
+        self.solves.iter().map(|solve| solve.moves).min()
+    }
+
+    /// Average time of the last `count` solves without the best and the worst
+    /// one, as is usual for puzzle competitions.
+    pub fn average_of(&self, count: usize) -> Option<f64> {
+        if count < 3 || self.solves.len() < count {
+            return None;
+        }
+
+        let mut times: Vec<f64> = self.solves[self.solves.len() - count..]
+            .iter()
+            .map(|solve| solve.time)
+            .collect();
+        times.sort_by(f64::total_cmp);
+
+        let counted = &times[1..count - 1];
+        Some(counted.iter().sum::<f64>() / counted.len() as f64)
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use super::*;
+    use crate::storage::{FileStorage, MemoryStorage};
+    use test_case::test_case;
+
+    fn solve(time: f64, moves: usize) -> Solve {
+        Solve {
+            seed: 1234,
+            moves,
+            time,
+            hints: 0,
+            date: 1_700_000_000.5,
+            size: BoardSize::STANDARD,
+            ruleset: Ruleset::Standard,
+        }
+    }
+
+    fn statistics(times: &[f64]) -> Statistics {
+        let mut statistics = Statistics::default();
+        for (i, time) in times.iter().enumerate() {
+            statistics.record(solve(*time, 100 - i));
+        }
+        statistics
+    }
+
+    #[test]
+    fn empty_statistics_have_no_bests() {
+        let statistics = Statistics::default();
+
+        assert_eq!(statistics.solve_count(), 0);
+        assert_eq!(statistics.best_time(), None);
+        assert_eq!(statistics.best_moves(), None);
+        assert_eq!(statistics.average_of(5), None);
+    }
+
+    #[test]
+    fn bests_are_tracked() {
+        let statistics = statistics(&[30.0, 12.5, 40.0]);
+
+        assert_eq!(statistics.solve_count(), 3);
+        assert_eq!(statistics.best_time(), Some(12.5));
+        assert_eq!(statistics.best_moves(), Some(98));
+    }
+
+    #[test]
+    fn average_drops_best_and_worst_of_the_last_solves() {
+        let statistics = statistics(&[1.0, 10.0, 20.0, 30.0, 40.0, 100.0]);
+
+        assert_eq!(statistics.average_of(5), Some(30.0));
+        assert_eq!(statistics.average_of(12), None);
+    }
+
+    #[test]
+    fn statistics_round_trip_through_file_storage() {
+        let directory = std::env::temp_dir()
+            .join("rubicross-tests")
+            .join(format!("statistics-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&directory);
+        let mut storage = FileStorage::new(directory);
+        let statistics = statistics(&[30.25, 12.5]);
+
+        statistics.save(&mut storage).unwrap();
+        let loaded = Statistics::load(&storage);
+
+        assert_eq!(loaded, statistics);
+    }
+
+    #[test]
+    fn bests_are_kept_per_board_size_and_rules() {
+        let mut statistics = statistics(&[30.0, 40.0]);
+        let large = Solve {
+            size: BoardSize::new(5).unwrap(),
+            ..solve(10.0, 500)
+        };
+        let checkerboard = Solve {
+            ruleset: Ruleset::Checkerboard,
+            ..solve(20.0, 50)
+        };
+        statistics.record(large.clone());
+        statistics.record(checkerboard.clone());
+
+        let standard = statistics.comparable_to(&solve(1.0, 1));
+        assert_eq!(standard.best_time(), Some(30.0));
+        assert_eq!(standard.best_moves(), Some(99));
+        assert_eq!(statistics.comparable_to(&large).solves(), &[large]);
+        assert_eq!(
+            statistics.comparable_to(&checkerboard).solves(),
+            &[checkerboard]
+        );
+    }
+
+    #[test]
+    fn target_rules_round_trip() {
+        let target = crate::ruleset::checkerboard(BoardSize::STANDARD);
+        let valid = Solve {
+            ruleset: Ruleset::Target(target),
+            ..solve(5.0, 20)
+        };
+
+        assert_eq!(Solve::from_line(&valid.to_line()), Some(valid));
+    }
+
+    #[test_case(OLD_HEADERS[0], "1234,20,5,0,1700000000.5"; "without the board size")]
+    #[test_case(OLD_HEADERS[1], "1234,20,5,0,1700000000.5,3"; "without the rules")]
+    fn old_solves_are_on_the_standard_board_by_the_standard_rules(header: &str, line: &str) {
+        let text = format!("{}\n{}\n", header, line);
+        let mut storage = MemoryStorage::default();
+        storage.save(STORAGE_KEY, &text).unwrap();
+
+        let statistics = Statistics::load(&storage);
+
+        assert_eq!(statistics.solves(), &[solve(5.0, 20)]);
+    }
+
+    #[test]
+    fn malformed_solves_are_skipped() {
+        let valid = solve(5.0, 20);
+        let text = format!(
+            "{}\n1,2,x,4,5\n{}\n1,2,3\n1,2,3,4,5,7\n1,2,3,4,5,3,chess\n\n",
+            HEADER,
+            valid.to_line()
+        );
+        let mut storage = MemoryStorage::default();
+        storage.save(STORAGE_KEY, &text).unwrap();
+
+        let statistics = Statistics::load(&storage);
+
+        assert_eq!(statistics.solves(), &[valid]);
+    }
+}
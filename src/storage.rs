@@ -0,0 +1,145 @@
+//! Persistent key-value storage. On desktop every key is a file in the user's
+//! data directory and on the web it is an entry in the browser's local storage.
+
+use std::io;
+
+pub trait Storage {
+    fn load(&self, key: &str) -> Option<String>;
+    fn save(&mut self, key: &str, value: &str) -> io::Result<()>;
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub use file::FileStorage;
+
+#[cfg(target_arch = "wasm32")]
+pub use local::LocalStorage;
+
+#[cfg(test)]
+pub use memory::MemoryStorage;
+
+/// The storage used by the game on the current platform.
+pub fn default_storage() -> Box<dyn Storage> {
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        let directory = dirs::data_dir()
+            .unwrap_or_else(std::env::temp_dir)
+            .join("rubicross");
+        Box::new(FileStorage::new(directory))
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    {
+        Box::new(LocalStorage)
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+mod file {
+    use std::{fs, io, path::PathBuf};
+
+    use super::Storage;
+
+    pub struct FileStorage {
+        directory: PathBuf,
+    }
+
+    impl FileStorage {
+        pub fn new(directory: PathBuf) -> Self {
+            Self { directory }
+        }
+
+        fn path(&self, key: &str) -> PathBuf {
+            self.directory.join(format!("{}.txt", key))
+        }
+    }
+
+    impl Storage for FileStorage {
+        fn load(&self, key: &str) -> Option<String> {
+            fs::read_to_string(self.path(key)).ok()
+        }
+
+        fn save(&mut self, key: &str, value: &str) -> io::Result<()> {
+            fs::create_dir_all(&self.directory)?;
+            fs::write(self.path(key), value)
+        }
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+mod local {
+    use std::io;
+
+    use super::Storage;
+
+    const KEY_PREFIX: &str = "rubicross.";
+
+    pub struct LocalStorage;
+
+    impl Storage for LocalStorage {
+        fn load(&self, key: &str) -> Option<String> {
+            quad_storage_sys::get(&format!("{}{}", KEY_PREFIX, key))
+        }
+
+        fn save(&mut self, key: &str, value: &str) -> io::Result<()> {
+            quad_storage_sys::set(&format!("{}{}", KEY_PREFIX, key), value);
+            Ok(())
+        }
+    }
+}
+
+/// Storage that only lasts as long as it is kept, for tests.
+#[cfg(test)]
+mod memory {
+    use std::{collections::HashMap, io};
+
+    use super::Storage;
+
+    #[derive(Default)]
+    pub struct MemoryStorage {
+        values: HashMap<String, String>,
+    }
+
+    impl Storage for MemoryStorage {
+        fn load(&self, key: &str) -> Option<String> {
+            self.values.get(key).cloned()
+        }
+
+        fn save(&mut self, key: &str, value: &str) -> io::Result<()> {
+            self.values.insert(key.into(), value.into());
+            Ok(())
+        }
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use super::*;
+
+    fn temporary_storage(name: &str) -> FileStorage {
+        let directory = std::env::temp_dir().join("rubicross-tests").join(format!(
+            "{}-{}",
+            name,
+            std::process::id()
+        ));
+        let _ = std::fs::remove_dir_all(&directory);
+
+        FileStorage::new(directory)
+    }
+
+    #[test]
+    fn missing_key_loads_nothing() {
+        let storage = temporary_storage("missing");
+
+        assert_eq!(storage.load("statistics"), None);
+    }
+
+    #[test]
+    fn saved_value_is_loaded() {
+        let mut storage = temporary_storage("saved");
+
+        storage.save("statistics", "first").unwrap();
+        storage.save("statistics", "second").unwrap();
+
+        assert_eq!(storage.load("statistics").as_deref(), Some("second"));
+    }
+}
@@ -0,0 +1,323 @@
+//! Art that replaces the embedded images without recompiling.
+//!
+//! A theme is a directory with a `theme.toml` manifest that names an image
+//! file for any of the embedded images, e.g.
+//!
+//! ```toml
+//! # Paths are relative to the theme directory
+//! board = "board.png"
+//! piece_yellow = "pieces/yellow.png"
+//! ```
+//!
+//! The names are the file names of the images in the `assets` directory
+//! without the extension. On desktop the directory is on the disk and on the
+//! web it's a path relative to the page. Images that aren't named in the
+//! manifest or fail to load are taken from the embedded defaults.
+//!
+//! On desktop every theme in the `themes` directory can be chosen on the
+//! settings screen.
+
+use std::{collections::HashMap, error::Error, fmt::Display};
+
+use macroquad::{file::load_file, texture::Image};
+
+use crate::settings::Settings;
+
+const MANIFEST_FILE_NAME: &str = "theme.toml";
+
+/// Directory with the themes to choose from, relative to where the game is
+/// started.
+#[cfg(not(target_arch = "wasm32"))]
+const THEMES_DIRECTORY: &str = "themes";
+
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum ThemeError {
+    ManifestNotLoaded(macroquad::Error),
+    ManifestNotText,
+    /// A manifest line that is not a `name = "path"` entry, counted from 1.
+    InvalidLine(usize),
+}
+
+impl Display for ThemeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ThemeError::ManifestNotLoaded(error) => {
+                write!(f, "Failed to load the theme manifest: {}", error)
+            }
+            ThemeError::ManifestNotText => write!(f, "Theme manifest is not valid UTF-8"),
+            ThemeError::InvalidLine(line) => write!(f, "Invalid theme manifest line {}", line),
+        }
+    }
+}
+
+impl Error for ThemeError {}
+
+/// Images loaded from a theme, by name.
+#[derive(Default)]
+pub struct Theme {
+    images: HashMap<String, Image>,
+}
+
+impl Theme {
+    /// Load the theme in the directory. Images that fail to load are
+    /// skipped, but the manifest itself has to be valid.
+    pub async fn load(directory: &str) -> Result<Self, ThemeError> {
+        let directory = directory.trim_end_matches('/');
+
+        let manifest = load_file(&format!("{}/{}", directory, MANIFEST_FILE_NAME))
+            .await
+            .map_err(ThemeError::ManifestNotLoaded)?;
+        let manifest = String::from_utf8(manifest).map_err(|_| ThemeError::ManifestNotText)?;
+
+        let mut images = HashMap::new();
+
+        for (name, path) in parse_manifest(&manifest)? {
+            let bytes = match load_file(&format!("{}/{}", directory, path)).await {
+                Ok(bytes) => bytes,
+                Err(error) => {
+                    println!("Failed to load theme image {}: {}", name, error);
+                    continue;
+                }
+            };
+
+            match Image::from_file_with_format(&bytes, None) {
+                Ok(image) => {
+                    images.insert(name, image);
+                }
+                Err(error) => println!("Failed to decode theme image {}: {}", name, error),
+            }
+        }
+
+        Ok(Self { images })
+    }
+
+    /// The image the theme has for the name, if any.
+    pub fn image(&self, name: &str) -> Option<&Image> {
+        self.images.get(name)
+    }
+}
+
+/// The themes to choose from, by directory, where `None` is the embedded art.
+/// The current theme is a choice even if it isn't in the `themes` directory,
+/// which is always the case on the web, where directories can't be listed.
+pub fn choices(current: Option<&str>) -> Vec<Option<String>> {
+    #[cfg(not(target_arch = "wasm32"))]
+    let installed = themes_in(std::path::Path::new(THEMES_DIRECTORY));
+    #[cfg(target_arch = "wasm32")]
+    let installed: Vec<String> = vec![];
+
+    let mut choices: Vec<Option<String>> = vec![None];
+    choices.extend(installed.into_iter().map(Some));
+
+    let current = current.map(str::to_string);
+    if !choices.contains(&current) {
+        choices.push(current);
+    }
+
+    choices
+}
+
+/// Which theme the games use. A theme given when starting the game is used
+/// instead of the one in the settings until another one is chosen there, and
+/// is never saved in the settings.
+#[derive(Debug)]
+pub struct ThemeSelection {
+    given: Option<String>,
+    /// The theme in the settings when the given one was last used.
+    chosen: Option<String>,
+}
+
+impl ThemeSelection {
+    pub fn new(given: Option<String>, settings: &Settings) -> Self {
+        Self {
+            given,
+            chosen: settings.theme.clone(),
+        }
+    }
+
+    /// Directory of the theme for a game with the settings, where `None` is
+    /// the embedded art.
+    pub fn directory(&mut self, settings: &Settings) -> Option<String> {
+        if settings.theme != self.chosen {
+            self.given = None;
+        }
+
+        self.given.clone().or_else(|| settings.theme.clone())
+    }
+}
+
+/// Short name of the theme in the directory, for the settings screen.
+pub fn display_name(directory: &str) -> &str {
+    directory
+        .trim_end_matches(['/', '\\'])
+        .rsplit(['/', '\\'])
+        .next()
+        .unwrap_or(directory)
+}
+
+/// The directories in the directory that have a manifest, sorted.
+#[cfg(not(target_arch = "wasm32"))]
+fn themes_in(directory: &std::path::Path) -> Vec<String> {
+    let Ok(entries) = std::fs::read_dir(directory) else {
+        return vec![];
+    };
+
+    let mut themes: Vec<String> = entries
+        .filter_map(Result::ok)
+        .map(|entry| entry.path())
+        .filter(|path| path.join(MANIFEST_FILE_NAME).is_file())
+        .filter_map(|path| path.to_str().map(str::to_string))
+        .collect();
+    themes.sort();
+
+    themes
+}
+
+/// The `(name, path)` entries of the manifest. Only the subset of TOML with
+/// string values and comments is supported.
+fn parse_manifest(text: &str) -> Result<Vec<(String, String)>, ThemeError> {
+    let mut entries = vec![];
+
+    for (index, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let invalid_line = || ThemeError::InvalidLine(index + 1);
+
+        let (name, value) = line.split_once('=').ok_or_else(invalid_line)?;
+        let value = value
+            .trim()
+            .strip_prefix('"')
+            .and_then(|value| value.strip_suffix('"'))
+            .ok_or_else(invalid_line)?;
+
+        let name = name.trim();
+        if name.is_empty() || value.is_empty() {
+            return Err(invalid_line());
+        }
+
+        entries.push((name.to_string(), value.to_string()));
+    }
+
+    Ok(entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::MemoryStorage;
+    use test_case::test_case;
+
+    #[test]
+    fn given_theme_is_used_but_not_saved() {
+        let mut storage = MemoryStorage::default();
+        let stored = Settings {
+            theme: Some("themes/light".into()),
+            ..Settings::default()
+        };
+        stored.save(&mut storage).unwrap();
+
+        let mut settings = Settings::load(&storage);
+        let mut selection = ThemeSelection::new(Some("themes/dark".into()), &settings);
+        assert_eq!(
+            selection.directory(&settings).as_deref(),
+            Some("themes/dark")
+        );
+
+        // Changing any other setting saves the settings
+        settings.colorblind_mode = true;
+        settings.save(&mut storage).unwrap();
+
+        assert_eq!(Settings::load(&storage).theme, stored.theme);
+        assert_eq!(
+            selection.directory(&settings).as_deref(),
+            Some("themes/dark")
+        );
+    }
+
+    #[test]
+    fn chosen_theme_replaces_the_given_one() {
+        let mut settings = Settings::default();
+        let mut selection = ThemeSelection::new(Some("themes/dark".into()), &settings);
+
+        settings.theme = Some("themes/light".into());
+        assert_eq!(
+            selection.directory(&settings).as_deref(),
+            Some("themes/light")
+        );
+
+        settings.theme = None;
+        assert_eq!(selection.directory(&settings), None);
+    }
+
+    #[test]
+    fn manifest_entries_are_parsed() {
+        let manifest =
+            "# Dark theme\n\nboard = \"board.png\"\n  piece_red =  \"pieces/red.png\" \n";
+
+        let entries = parse_manifest(manifest).unwrap();
+
+        assert_eq!(
+            entries,
+            vec![
+                ("board".to_string(), "board.png".to_string()),
+                ("piece_red".to_string(), "pieces/red.png".to_string()),
+            ]
+        );
+    }
+
+    #[test_case("board = board.png", 1; "unquoted path")]
+    #[test_case("# comment\nboard", 2; "missing value")]
+    #[test_case("board = \"\"", 1; "empty path")]
+    #[test_case(" = \"board.png\"", 1; "missing name")]
+    fn invalid_manifest_line_is_reported(manifest: &str, expected: usize) {
+        let result = parse_manifest(manifest);
+
+        assert!(matches!(result, Err(ThemeError::InvalidLine(line)) if line == expected));
+    }
+
+    #[test_case("themes/dark", "dark")]
+    #[test_case("themes/dark/", "dark"; "trailing slash")]
+    #[test_case("dark", "dark"; "no parent")]
+    fn theme_is_shown_by_its_directory_name(directory: &str, expected: &str) {
+        assert_eq!(display_name(directory), expected);
+    }
+
+    #[test]
+    fn current_theme_is_always_a_choice() {
+        let choices = choices(Some("elsewhere/light"));
+
+        assert_eq!(choices.first(), Some(&None));
+        assert_eq!(choices.last(), Some(&Some("elsewhere/light".to_string())));
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    #[test]
+    fn only_directories_with_a_manifest_are_themes() {
+        let directory = std::env::temp_dir()
+            .join("rubicross-tests")
+            .join(format!("themes-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&directory);
+        for name in ["light", "dark", "empty"] {
+            std::fs::create_dir_all(directory.join(name)).unwrap();
+        }
+        for name in ["light", "dark"] {
+            std::fs::write(directory.join(name).join(MANIFEST_FILE_NAME), "").unwrap();
+        }
+
+        let names: Vec<_> = themes_in(&directory)
+            .iter()
+            .map(|theme| display_name(theme).to_string())
+            .collect();
+
+        assert_eq!(names, vec!["dark", "light"]);
+    }
+
+    #[test]
+    fn empty_theme_has_no_images() {
+        assert!(Theme::default().image("board").is_none());
+    }
+}
@@ -0,0 +1,400 @@
+//! Drawing with shapes instead of images.
+//!
+//! Everything the images in `assets` show is drawn with macroquad's shape
+//! primitives, so it stays sharp at any scale. The layout is the same as the
+//! one of the images, see [piece_location].
+
+use std::f32::consts::TAU;
+
+use macroquad::{
+    color::Color,
+    color_u8,
+    math::Vec2,
+    shapes::{draw_arc, draw_line, draw_poly, draw_rectangle, draw_triangle},
+    text::{draw_text, measure_text},
+};
+
+use crate::{
+    button::ButtonId,
+    initialization::{piece_location, piece_scale, piece_size, PIECE_DISTANCE, PIECE_SIZE},
+    pieces::position::Square,
+    viewport::BOARD_SIZE,
+    BoardSize, BoardState, Position,
+};
+
+const CENTER: f32 = BOARD_SIZE / 2.;
+/// Radius of the dark disk the pieces move on.
+const BOARD_RADIUS: f32 = 205.;
+/// Sides of the polygons that stand in for circles.
+const CIRCLE_SIDES: u8 = 96;
+
+const DARK: Color = color_u8!(0x21, 0x21, 0x21, 0xff);
+const GREY: Color = color_u8!(0x67, 0x67, 0x67, 0xff);
+const SLOT: Color = color_u8!(0x2a, 0x2a, 0x2a, 0xff);
+const BACKGROUND: Color = color_u8!(0xc5, 0xba, 0xaf, 0xff);
+const HIGHLIGHT: Color = color_u8!(0xff, 0xe6, 0x6d, 0xff);
+const PRESSED: Color = color_u8!(0x51, 0x25, 0x00, 0xff);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ButtonState {
+    Idle,
+    Hovered,
+    Pressed,
+}
+
+/// A texture-sized area scaled and rotated the same way as [draw_texture_ex]
+/// draws a texture, in which shapes are given in the same coordinates as on
+/// the texture.
+///
+/// [draw_texture_ex]: macroquad::texture::draw_texture_ex
+struct Frame {
+    position: Vec2,
+    size: Vec2,
+    rotation: f32,
+    pivot: Option<Vec2>,
+    scale: f32,
+}
+
+impl Frame {
+    fn point(&self, x: f32, y: f32) -> Vec2 {
+        let pivot = self
+            .pivot
+            .unwrap_or(self.position + self.size * self.scale / 2.);
+        let point = self.position + Vec2::new(x, y) * self.scale;
+        pivot + Vec2::from_angle(self.rotation).rotate(point - pivot)
+    }
+
+    fn polygon(&self, points: &[(f32, f32)], color: Color) {
+        let points: Vec<_> = points.iter().map(|(x, y)| self.point(*x, *y)).collect();
+
+        // Fan triangulation, which is enough for the convex and star-shaped outlines used
+        for pair in points[1..].windows(2) {
+            draw_triangle(points[0], pair[0], pair[1], color);
+        }
+    }
+}
+
+fn with_alpha(color: Color, alpha: f32) -> Color {
+    Color { a: alpha, ..color }
+}
+
+fn draw_disk(x: f32, y: f32, radius: f32, color: Color) {
+    draw_poly(x, y, CIRCLE_SIDES, radius, 0., color);
+}
+
+/// The dark disk with a slot for every piece.
+pub fn draw_board(size: BoardSize) {
+    draw_disk(CENTER, CENTER, BOARD_RADIUS, DARK);
+
+    for position in Position::all(size) {
+        let (x, y) = piece_location(size, position.row() as i32, position.col() as i32);
+        let radius = piece_size(size) / 2.;
+        draw_disk(x + radius, y + radius, radius + 1., SLOT);
+    }
+}
+
+/// Cover everything outside the board, including the pieces sliding out of it.
+pub fn draw_surroundings() {
+    let corner_distance = CENTER * 2f32.sqrt();
+
+    draw_arc(
+        CENTER,
+        CENTER,
+        CIRCLE_SIDES,
+        BOARD_RADIUS,
+        0.,
+        corner_distance - BOARD_RADIUS + 1.,
+        360.,
+        BACKGROUND,
+    );
+}
+
+/// The light and the dark shade of the color of a piece.
+fn piece_shades(color: crate::Color) -> (Color, Color) {
+    use crate::Color::*;
+
+    match color {
+        Yellow => (
+            color_u8!(0xff, 0xc1, 0x45, 0xff),
+            color_u8!(0x57, 0x39, 0x00, 0xff),
+        ),
+        Blue => (
+            color_u8!(0x44, 0x9d, 0xd1, 0xff),
+            color_u8!(0x16, 0x40, 0x58, 0xff),
+        ),
+        Red => (
+            color_u8!(0xeb, 0x51, 0x60, 0xff),
+            color_u8!(0x72, 0x0d, 0x15, 0xff),
+        ),
+        Purple => (
+            color_u8!(0x8f, 0x65, 0x93, 0xff),
+            color_u8!(0x3c, 0x2b, 0x3e, 0xff),
+        ),
+        Green => (
+            color_u8!(0x04, 0xa7, 0x77, 0xff),
+            color_u8!(0x02, 0x42, 0x2f, 0xff),
+        ),
+    }
+}
+
+/// A piece of the given width with its top left corner at the point.
+pub fn draw_piece(color: crate::Color, x: f32, y: f32, size: f32) {
+    let (light, dark) = piece_shades(color);
+    let scale = size / PIECE_SIZE;
+    let radius = size / 2.;
+    let (center_x, center_y) = (x + radius, y + radius);
+
+    draw_disk(center_x, center_y, radius, light);
+    draw_arc(
+        center_x,
+        center_y,
+        CIRCLE_SIDES,
+        radius - 4. * scale,
+        0.,
+        2.5 * scale,
+        360.,
+        dark,
+    );
+}
+
+/// Marks on the corners between the pieces of a solved square. The side of
+/// an outer square that faces away from the center is left unmarked.
+pub fn draw_solved_marker(square: Square, size: BoardSize) {
+    const ARM: f32 = 4.;
+    const THICKNESS: f32 = 2.;
+
+    let s = size.square_size() as i32;
+    let (first_row, first_col) = match square {
+        Square::North => (0, s),
+        Square::West => (s, 0),
+        Square::Center => (s, s),
+        Square::East => (s, 2 * s),
+        Square::South => (2 * s, s),
+    };
+
+    for i in 0..=s {
+        for j in 0..=s {
+            let skipped = match square {
+                Square::North => i == 0,
+                Square::South => i == s,
+                Square::West => j == 0,
+                Square::East => j == s,
+                Square::Center => false,
+            };
+
+            if skipped {
+                continue;
+            }
+
+            let (x, y) = piece_location(size, first_row + i, first_col + j);
+            let gap = (PIECE_DISTANCE - PIECE_SIZE) * piece_scale(size);
+            let (x, y) = (x - gap / 2., y - gap / 2.);
+
+            draw_line(x - ARM, y, x + ARM, y, THICKNESS, HIGHLIGHT);
+            draw_line(x, y - ARM, x, y + ARM, THICKNESS, HIGHLIGHT);
+        }
+    }
+}
+
+/// A small picture of the board the rules want to be reached, with its top
+/// edge centered on the point and `cell` pixels for every piece.
+pub fn draw_target(target: &BoardState, center_x: f32, top_y: f32, cell: f32) {
+    let left = center_x - target.size().dimension() as f32 * cell / 2.;
+
+    for (position, color) in target.iter() {
+        let (light, _) = piece_shades(color);
+        draw_rectangle(
+            left + position.col() as f32 * cell,
+            top_y + position.row() as f32 * cell,
+            cell,
+            cell,
+            light,
+        );
+    }
+}
+
+/// The box shown over the board once the puzzle is solved.
+pub fn draw_victory_marker() {
+    const SIZE: f32 = 126.;
+    const FONT_SIZE: u16 = 44;
+    let corner = CENTER - SIZE / 2.;
+
+    draw_rectangle(corner + 2., corner + 2., SIZE, SIZE, DARK);
+    draw_rectangle(corner, corner, SIZE, SIZE, GREY);
+
+    for (text, baseline) in [("YOU", CENTER - 10.), ("WIN", CENTER + 38.)] {
+        let dimensions = measure_text(text, None, FONT_SIZE, 1.0);
+        draw_text(
+            text,
+            CENTER - dimensions.width / 2.,
+            baseline,
+            FONT_SIZE as f32,
+            HIGHLIGHT,
+        );
+    }
+}
+
+/// A button with the top left corner of its unrotated area at `position`,
+/// rotated around `pivot` or its center and scaled by `scale`. `alpha` makes
+/// it transparent, e.g. for a pulsing highlight.
+pub fn draw_button(
+    id: ButtonId,
+    position: Vec2,
+    rotation: f32,
+    pivot: Option<Vec2>,
+    scale: f32,
+    state: ButtonState,
+    alpha: f32,
+) {
+    let fill = match state {
+        ButtonState::Idle => None,
+        ButtonState::Hovered => Some(with_alpha(HIGHLIGHT, alpha)),
+        ButtonState::Pressed => Some(with_alpha(PRESSED, alpha)),
+    };
+
+    match id {
+        ButtonId::HorizontalLeft(_)
+        | ButtonId::HorizontalRight(_)
+        | ButtonId::VerticalUp(_)
+        | ButtonId::VerticalDown(_) => {
+            let frame = Frame {
+                position,
+                size: Vec2::new(36., 38.),
+                rotation,
+                pivot,
+                scale,
+            };
+            draw_arrow(
+                &frame,
+                &[(18., 1.), (35., 37.), (18., 29.), (1., 37.)],
+                with_alpha(DARK, alpha),
+                fill,
+            );
+        }
+        ButtonId::RotateClockwise(_) | ButtonId::RotateAnticlockwise(_) => {
+            let frame = Frame {
+                position,
+                size: Vec2::new(34., 34.),
+                rotation,
+                pivot,
+                scale,
+            };
+            draw_arrow(
+                &frame,
+                &[(33., 1.), (20., 33.), (17., 17.), (1., 14.)],
+                with_alpha(GREY, alpha),
+                fill,
+            );
+        }
+        ButtonId::Hint | ButtonId::Undo | ButtonId::Redo | ButtonId::Settings => {
+            let icon_color = with_alpha(fill.unwrap_or(BACKGROUND), alpha);
+            draw_utility_button(
+                id,
+                position.x,
+                position.y,
+                with_alpha(DARK, alpha),
+                icon_color,
+            );
+        }
+    }
+}
+
+/// An arrowhead outline, filled with a smaller copy of itself when the
+/// button is hovered or pressed.
+fn draw_arrow(frame: &Frame, outline: &[(f32, f32)], color: Color, fill: Option<Color>) {
+    const FILL_SCALE: f32 = 0.55;
+
+    frame.polygon(outline, color);
+
+    let Some(fill) = fill else {
+        return;
+    };
+
+    let count = outline.len() as f32;
+    let centroid_x = outline.iter().map(|(x, _)| x).sum::<f32>() / count;
+    let centroid_y = outline.iter().map(|(_, y)| y).sum::<f32>() / count;
+
+    let inner: Vec<_> = outline
+        .iter()
+        .map(|(x, y)| {
+            (
+                centroid_x + (x - centroid_x) * FILL_SCALE,
+                centroid_y + (y - centroid_y) * FILL_SCALE,
+            )
+        })
+        .collect();
+
+    frame.polygon(&inner, fill);
+}
+
+fn draw_utility_button(id: ButtonId, x: f32, y: f32, color: Color, icon_color: Color) {
+    const SIZE: f32 = 38.;
+    const RADIUS: f32 = 8.;
+    const THICKNESS: f32 = 3.;
+    let (center_x, center_y) = (x + SIZE / 2., y + SIZE / 2.);
+
+    draw_disk(center_x, center_y, SIZE / 2., color);
+
+    match id {
+        ButtonId::Hint => {
+            const FONT_SIZE: u16 = 32;
+            let dimensions = measure_text("?", None, FONT_SIZE, 1.0);
+            draw_text(
+                "?",
+                center_x - dimensions.width / 2.,
+                center_y + dimensions.height / 2.,
+                FONT_SIZE as f32,
+                icon_color,
+            );
+        }
+        ButtonId::Undo | ButtonId::Redo => {
+            // Three quarters of a circle starting at the top, with an arrowhead
+            // pointing back along it. Redo is the mirror image of undo.
+            let direction = if matches!(id, ButtonId::Undo) {
+                -1.
+            } else {
+                1.
+            };
+            let arc_start = if direction < 0. { -90. } else { 0. };
+
+            draw_arc(
+                center_x,
+                center_y + 2.,
+                CIRCLE_SIDES / 4,
+                RADIUS - THICKNESS / 2.,
+                arc_start,
+                THICKNESS,
+                270.,
+                icon_color,
+            );
+
+            let tip_y = center_y + 2. - RADIUS;
+            draw_triangle(
+                Vec2::new(center_x + direction * 7., tip_y),
+                Vec2::new(center_x, tip_y - 6.),
+                Vec2::new(center_x, tip_y + 6.),
+                icon_color,
+            );
+        }
+        ButtonId::Settings => {
+            // A gear made of two overlapping squares for the teeth, with a hole
+            draw_poly(center_x, center_y, 4, 11., 0., icon_color);
+            draw_poly(center_x, center_y, 4, 11., 45., icon_color);
+            draw_disk(center_x, center_y, 8., icon_color);
+            draw_disk(center_x, center_y, 3.5, color);
+        }
+        _ => unreachable!("Only utility buttons have icons"),
+    }
+
+    // Keep the circle crisp against the background
+    draw_arc(
+        center_x,
+        center_y,
+        CIRCLE_SIDES,
+        SIZE / 2. - 1.,
+        0.,
+        1.,
+        TAU.to_degrees(),
+        color,
+    );
+}
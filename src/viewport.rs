@@ -0,0 +1,94 @@
+//! Scaling of the board to the window.
+//!
+//! Everything is laid out on a 500x500 board. The board is scaled to fit
+//! the window while keeping its aspect ratio and the rest of the window is
+//! left as bars on the sides.
+
+use macroquad::{
+    camera::{set_camera, Camera2D},
+    color::Color,
+    color_u8,
+    math::Rect,
+    window::{clear_background, screen_height, screen_width},
+};
+
+/// Width and height of the board that everything is laid out on.
+pub const BOARD_SIZE: f32 = 500.;
+
+const BAR_COLOR: Color = color_u8!(0x21, 0x21, 0x21, 0xff);
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Viewport {
+    /// Location of the board's top left corner in the window.
+    pub x: f32,
+    pub y: f32,
+    /// Window pixels per board unit.
+    pub scale: f32,
+}
+
+impl Viewport {
+    /// The largest board that fits in a window of the given size, centered.
+    pub fn fit(width: f32, height: f32) -> Self {
+        let scale = (width.min(height) / BOARD_SIZE).max(f32::EPSILON);
+
+        Self {
+            x: (width - BOARD_SIZE * scale) / 2.,
+            y: (height - BOARD_SIZE * scale) / 2.,
+            scale,
+        }
+    }
+
+    /// The viewport for the current window size.
+    pub fn current() -> Self {
+        Self::fit(screen_width(), screen_height())
+    }
+
+    /// Convert a point in the window, e.g. the mouse position, to the board.
+    pub fn to_board(&self, x: f32, y: f32) -> (f32, f32) {
+        ((x - self.x) / self.scale, (y - self.y) / self.scale)
+    }
+
+    /// Clear the window and make the following drawing happen on the board.
+    /// Anything drawn outside the board is cut off.
+    pub fn apply(&self) {
+        clear_background(BAR_COLOR);
+
+        let size = (BOARD_SIZE * self.scale).round() as i32;
+        let x = self.x.round() as i32;
+        // The viewport is counted from the bottom of the window
+        let y = (screen_height() - self.y - BOARD_SIZE * self.scale).round() as i32;
+
+        let mut camera = Camera2D::from_display_rect(Rect::new(0., 0., BOARD_SIZE, BOARD_SIZE));
+        camera.viewport = Some((x, y, size, size));
+
+        set_camera(&camera);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_case::test_case;
+
+    #[test_case(500., 500., 0., 0., 1.; "design size")]
+    #[test_case(1000., 1000., 0., 0., 2.; "scaled up")]
+    #[test_case(1000., 500., 250., 0., 1.; "bars on the left and right")]
+    #[test_case(250., 400., 0., 75., 0.5; "bars on the top and bottom")]
+    fn viewport_fits_the_window(width: f32, height: f32, x: f32, y: f32, scale: f32) {
+        assert_eq!(Viewport::fit(width, height), Viewport { x, y, scale });
+    }
+
+    #[test_case(1000., 500., 250., 0., (0., 0.); "top left corner")]
+    #[test_case(1000., 500., 750., 500., (500., 500.); "bottom right corner")]
+    #[test_case(250., 400., 125., 200., (250., 250.); "center")]
+    #[test_case(1000., 500., 100., 10., (-150., 10.); "bar")]
+    fn window_point_is_mapped_to_the_board(
+        width: f32,
+        height: f32,
+        x: f32,
+        y: f32,
+        expected: (f32, f32),
+    ) {
+        assert_eq!(Viewport::fit(width, height).to_board(x, y), expected);
+    }
+}